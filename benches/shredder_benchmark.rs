@@ -1,12 +1,15 @@
 use std::cell::RefCell;
 
 use criterion::criterion_group;
-use criterion::{black_box, criterion_main, Criterion};
+use criterion::{black_box, criterion_main, BenchmarkId, Criterion};
 use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use rand::SeedableRng;
 
-use shredder::{collect, number_of_tracked_allocations, run_with_gc_cleanup, Gc, Scan};
+use shredder::{
+    collect, number_of_tracked_allocations, run_with_gc_cleanup, set_gc_parallelism, Gc,
+    GcParallelism, Scan,
+};
 
 // BENCHMARK 1: My janky stress test
 // (It basically creates a graph where every node is rooted, then de-roots some nodes a few at a time)
@@ -115,9 +118,94 @@ pub fn benchmark_count_binary_trees(c: &mut Criterion) {
     });
 }
 
+// BENCHMARK 3: Marking deep and wide graphs, with more and more marking threads
+// (Everything stays live, so each collection is basically all marking)
+
+#[derive(Scan)]
+struct GraphNode {
+    children: Vec<Gc<GraphNode>>,
+}
+
+const DEEP_LENGTH: usize = 1 << 16;
+const WIDE_FANOUT: usize = 40;
+
+/// A long linked list (so there's little parallelism to find)
+fn deep_graph() -> Gc<GraphNode> {
+    let mut head = Gc::new(GraphNode {
+        children: Vec::new(),
+    });
+    for _ in 1..DEEP_LENGTH {
+        head = Gc::new(GraphNode {
+            children: vec![head],
+        });
+    }
+    head
+}
+
+/// A shallow tree, about as big as the deep graph (so there's plenty of parallelism)
+fn wide_graph() -> Gc<GraphNode> {
+    let leaves = |_| {
+        Gc::new(GraphNode {
+            children: (0..WIDE_FANOUT)
+                .map(|_| {
+                    Gc::new(GraphNode {
+                        children: Vec::new(),
+                    })
+                })
+                .collect(),
+        })
+    };
+
+    Gc::new(GraphNode {
+        children: (0..WIDE_FANOUT)
+            .map(|_| {
+                Gc::new(GraphNode {
+                    children: (0..WIDE_FANOUT).map(leaves).collect(),
+                })
+            })
+            .collect(),
+    })
+}
+
+fn benchmark_marking(c: &mut Criterion, name: &str, make_graph: fn() -> Gc<GraphNode>) {
+    run_with_gc_cleanup(|| {
+        let graph = make_graph();
+        let mut group = c.benchmark_group(name);
+        group.sample_size(20);
+
+        set_gc_parallelism(GcParallelism::Sequential);
+        group.bench_function("sequential", |b| b.iter(collect));
+
+        for threads in [1, 2, 4, 8] {
+            set_gc_parallelism(GcParallelism::private_pool(threads).unwrap());
+            group.bench_with_input(BenchmarkId::new("threads", threads), &threads, |b, _| {
+                b.iter(collect)
+            });
+        }
+
+        group.finish();
+        set_gc_parallelism(GcParallelism::GlobalPool);
+        drop(graph);
+    });
+}
+
+pub fn benchmark_mark_deep_graph(c: &mut Criterion) {
+    benchmark_marking(c, "mark deep graph", deep_graph);
+}
+
+pub fn benchmark_mark_wide_graph(c: &mut Criterion) {
+    benchmark_marking(c, "mark wide graph", wide_graph);
+}
+
 // TODO: Benchmark with circular references
 // TODO: Benchmark with DerefGc
 // TODO: Do we want to cleanup in the benchmark?
 
-criterion_group!(benches, benchmark_stress_test, benchmark_count_binary_trees);
+criterion_group!(
+    benches,
+    benchmark_stress_test,
+    benchmark_count_binary_trees,
+    benchmark_mark_deep_graph,
+    benchmark_mark_wide_graph
+);
 criterion_main!(benches);
@@ -1,12 +1,11 @@
 use std::marker::PhantomData;
-use std::mem;
-use std::ptr::drop_in_place;
+use std::ptr::{self, drop_in_place};
 use std::sync::atomic::{AtomicPtr, Ordering};
 use std::sync::Arc;
 
 use crate::collector::{GcData, InternalGcRef, COLLECTOR};
-use crate::marker::{GcDeref, GcSafe};
-use crate::{Finalize, Gc, Scan, Scanner};
+use crate::marker::{GcDeref, GcDrop, GcSafe};
+use crate::{Finalize, Gc, GcClone, GcCloner, Scan, Scanner};
 
 /// An atomic `Gc<T>`, useful for concurrent algorithms
 ///
@@ -16,15 +15,14 @@ use crate::{Finalize, Gc, Scan, Scanner};
 /// A good analogy would be to the excellent `arc-swap` crate. However, we can be more performant,
 /// as relying on the collector lets us avoid some synchronization.
 ///
-/// `AtomicGc` should be fairly fast, but you may not assume it does not block. In fact in the
-/// presence of an active garbage collection operation, all operations will block. Otherwise
-/// it shouldn't block.
+/// `AtomicGc` operations don't wait for collection. While a collection is marking, they tell the
+/// collector about any data they read or overwrite (a snapshot-at-the-beginning deletion barrier),
+/// so that data can't be lost. The only time they wait is for the brief moments when a collection
+/// starts or stops marking.
 #[derive(Clone, Debug)]
 pub struct AtomicGc<T: Scan> {
-    // It is only safe to read the data here if a collection is not happening
-    atomic_ptr: Arc<AtomicPtr<GcData>>,
-    backing_handle: InternalGcRef,
-    _mark: PhantomData<Gc<T>>,
+    // An `AtomicOptionGc` we never store `None` into
+    inner: AtomicOptionGc<T>,
 }
 
 impl<T: Scan> AtomicGc<T> {
@@ -33,27 +31,18 @@ impl<T: Scan> AtomicGc<T> {
     /// The created `AtomicGc` will point to the same data as `data`
     #[must_use]
     pub fn new(data: &Gc<T>) -> Self {
-        // Ensure we don't create an atomic out of dead data...
-        data.assert_live();
-
-        // `data` is guaranteed to be pointing to the data we're about to contain, so we don't need to
-        // worry about data getting cleaned up (and therefore we don't need to block the collector)
-
-        // Carefully craft a ptr to store atomically
-        let data_arc = data.internal_handle_ref().data();
-        let data_ptr = Arc::as_ptr(data_arc);
-
-        let atomic_ptr = Arc::new(AtomicPtr::new(data_ptr as _));
-
         Self {
-            atomic_ptr: atomic_ptr.clone(),
-            backing_handle: COLLECTOR.new_handle_for_atomic(atomic_ptr),
-            _mark: PhantomData,
+            inner: AtomicOptionGc::new(Some(data)),
         }
     }
 
-    pub(crate) fn internal_handle(&self) -> InternalGcRef {
-        self.backing_handle.clone()
+    /// Create a new `AtomicGc`, without checking that `data` is live
+    ///
+    /// This is useful for data that is still being initialized by `Gc::new_cyclic`
+    pub(crate) fn new_unchecked(data: &Gc<T>) -> Self {
+        Self {
+            inner: AtomicOptionGc::new_unchecked(Some(data)),
+        }
     }
 
     /// `load` the data from this `AtomicGc<T>`, getting back a `Gc<T>`
@@ -61,49 +50,16 @@ impl<T: Scan> AtomicGc<T> {
     /// The ordering/atomicity guarantees are identical to `AtomicPtr::load`
     #[must_use]
     pub fn load(&self, ordering: Ordering) -> Gc<T> {
-        let ptr;
-        let internal_handle;
-        {
-            let _collection_blocker = COLLECTOR.get_collection_blocker_spinlock();
-
-            // Safe to manipulate this ptr only because we have the `_collection_blocker`
-            // (And we know this `Arc` still has a pointer in the collector data structures,
-            // otherwise someone would be accessing an `AtomicGc` pointing to freed data--which
-            // is impossible in safe code.)
-            let gc_data_ptr = self.atomic_ptr.load(ordering);
-            let gc_data_temp = unsafe { Arc::from_raw(gc_data_ptr) };
-
-            // Create a new `Arc` pointing to the same data, but don't invalidate the existing `Arc`
-            // (which is effectively "behind" the pointer)
-            let new_gc_data_ref = gc_data_temp.clone();
-            mem::forget(gc_data_temp);
-
-            ptr = new_gc_data_ref.scan_ptr().cast();
-            internal_handle = COLLECTOR.handle_from_data(new_gc_data_ref);
-        }
-
-        Gc::new_raw(internal_handle, ptr)
+        self.inner
+            .load(ordering)
+            .unwrap_or_else(|| unreachable!("an `AtomicGc` is never null"))
     }
 
     /// `store` new data into this `AtomicGc`
     ///
     /// The ordering/atomicity guarantees are identical to `AtomicPtr::store`
     pub fn store(&self, v: &Gc<T>, ordering: Ordering) {
-        // Ensure we're not storing dead data...
-        v.assert_live();
-
-        let data = v.internal_handle_ref().data();
-        let raw_data_ptr = Arc::as_ptr(data);
-
-        {
-            let _collection_blocker = COLLECTOR.get_collection_blocker_spinlock();
-
-            // Safe to manipulate this ptr only because we have the `_collection_blocker`
-            // (And we know this `Arc` still has a pointer in the collector data structures,
-            // otherwise someone would be accessing an `AtomicGc` pointing to freed data--which
-            // is impossible in safe code.)
-            self.atomic_ptr.store(raw_data_ptr as _, ordering);
-        }
+        self.inner.store(Some(v), ordering);
     }
 
     /// `swap` what data is stored in this `AtomicGc`, getting a `Gc` to the old data back
@@ -111,31 +67,9 @@ impl<T: Scan> AtomicGc<T> {
     /// The ordering/atomicity guarantees are identical to `AtomicPtr::swap`
     #[must_use]
     pub fn swap(&self, v: &Gc<T>, ordering: Ordering) -> Gc<T> {
-        // Ensure we're not storing dead data...
-        v.assert_live();
-
-        let data = v.internal_handle_ref().data();
-        let raw_data_ptr = Arc::as_ptr(data);
-
-        let ptr;
-        let internal_handle;
-        {
-            let _collection_blocker = COLLECTOR.get_collection_blocker_spinlock();
-            let old_data_ptr = self.atomic_ptr.swap(raw_data_ptr as _, ordering);
-
-            // Safe to manipulate this ptr only because we have the `_collection_blocker`
-            // (And we know this `Arc` still has a pointer in the collector data structures,
-            // otherwise someone would be accessing an `AtomicGc` pointing to freed data--which
-            // is impossible in safe code.)
-            let old_data_arc = unsafe { Arc::from_raw(old_data_ptr) };
-            let gc_data = old_data_arc.clone();
-            mem::forget(old_data_arc);
-
-            ptr = gc_data.scan_ptr().cast();
-            internal_handle = COLLECTOR.handle_from_data(gc_data);
-        }
-
-        Gc::new_raw(internal_handle, ptr)
+        self.inner
+            .swap(Some(v), ordering)
+            .unwrap_or_else(|| unreachable!("an `AtomicGc` is never null"))
     }
 
     /// Do a CAS operation. If this `AtomicGc` points to the same data as `current` then after this
@@ -150,32 +84,15 @@ impl<T: Scan> AtomicGc<T> {
     /// Returns `true` if the swap happened and this `AtomicGc` now points to `new`
     /// Returns `false` if the swap failed / this `AtomicGc` was not pointing to `current`
     #[allow(clippy::must_use_candidate)]
-    #[allow(deprecated)]
     pub fn compare_and_swap(&self, current: &Gc<T>, new: &Gc<T>, ordering: Ordering) -> bool {
-        // Ensure we're not storing dead data...
-        new.assert_live();
-
-        // Turn guess data into a raw ptr
-        let guess_data = current.internal_handle_ref().data();
-        let guess_data_raw = Arc::as_ptr(guess_data) as _;
-
-        // Turn new data into a raw ptr
-        let new_data = new.internal_handle_ref().data();
-        let new_data_raw = Arc::as_ptr(new_data) as _;
-
-        let compare_res;
-        {
-            let _collection_blocker = COLLECTOR.get_collection_blocker_spinlock();
-            // Safe to manipulate this ptr only because we have the `_collection_blocker`
-            // (And we know this `Arc` still has a pointer in the collector data structures,
-            // otherwise someone would be accessing an `AtomicGc` pointing to freed data--which
-            // is impossible in safe code.)
-            compare_res = self
-                .atomic_ptr
-                .compare_and_swap(guess_data_raw, new_data_raw, ordering);
-        }
-
-        compare_res == guess_data_raw
+        // `compare_and_swap` picks the strongest failure ordering it can for us
+        let failure = match ordering {
+            Ordering::Release => Ordering::Relaxed,
+            Ordering::AcqRel => Ordering::Acquire,
+            ordering => ordering,
+        };
+
+        self.compare_exchange(current, new, ordering, failure)
     }
 
     /// Do a CAE operation. If this `AtomicGc` points to the same data as `current` then after this
@@ -198,28 +115,8 @@ impl<T: Scan> AtomicGc<T> {
         success: Ordering,
         failure: Ordering,
     ) -> bool {
-        // Ensure we're not storing dead data...
-        new.assert_live();
-
-        let guess_data = current.internal_handle_ref().data();
-        let guess_data_raw = Arc::as_ptr(guess_data) as _;
-
-        let new_data = new.internal_handle_ref().data();
-        let new_data_raw = Arc::as_ptr(new_data) as _;
-
-        let swap_result;
-        {
-            let _collection_blocker = COLLECTOR.get_collection_blocker_spinlock();
-            // Safe to manipulate this ptr only because we have the `_collection_blocker`
-            // (And we know this `Arc` still has a pointer in the collector data structures,
-            // otherwise someone would be accessing an `AtomicGc` pointing to freed data--which
-            // is impossible in safe code.)
-            swap_result =
-                self.atomic_ptr
-                    .compare_exchange(guess_data_raw, new_data_raw, success, failure);
-        }
-
-        swap_result.is_ok()
+        self.inner
+            .compare_exchange(Some(current), Some(new), success, failure)
     }
 
     // TODO: Compare and swap/compare and exchange that return the current value
@@ -227,7 +124,16 @@ impl<T: Scan> AtomicGc<T> {
 
 unsafe impl<T: Scan> Scan for AtomicGc<T> {
     fn scan(&self, scanner: &mut Scanner<'_>) {
-        scanner.add_internal_handle(self.internal_handle());
+        self.inner.scan(scanner);
+    }
+}
+
+// The copy points to a copy of whatever this `AtomicGc` points to when it is cloned
+impl<T: GcClone + GcDrop + 'static> GcClone for AtomicGc<T> {
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        Self {
+            inner: self.inner.gc_clone(cloner),
+        }
     }
 }
 
@@ -241,9 +147,207 @@ unsafe impl<T: Scan> Finalize for AtomicGc<T> {
     }
 }
 
-impl<T: Scan> Drop for AtomicGc<T> {
+/// A nullable `AtomicGc<T>`, for lock-free data structures that need an empty state
+///
+/// This works just like `AtomicGc`, but can also hold `None`. That makes it useful for things like
+/// the `next` pointer at the end of a linked list, or the head of an empty stack.
+///
+/// # Example
+/// ```
+/// use std::sync::atomic::Ordering;
+///
+/// use shredder::atomic::AtomicOptionGc;
+/// use shredder::Gc;
+///
+/// let atomic = AtomicOptionGc::none();
+/// assert!(atomic.load(Ordering::SeqCst).is_none());
+///
+/// let data = Gc::new(5);
+/// assert!(atomic.compare_exchange(None, Some(&data), Ordering::SeqCst, Ordering::SeqCst));
+/// assert!(atomic.take(Ordering::SeqCst).unwrap().ptr_eq(&data));
+/// ```
+#[derive(Clone, Debug)]
+pub struct AtomicOptionGc<T: Scan> {
+    // Only access this through the collector, so the deletion barrier runs
+    atomic_ptr: Arc<AtomicPtr<GcData>>,
+    backing_handle: InternalGcRef,
+    _mark: PhantomData<Gc<T>>,
+}
+
+impl<T: Scan> AtomicOptionGc<T> {
+    /// Create a new `AtomicOptionGc`
+    ///
+    /// The created `AtomicOptionGc` will point to the same data as `data`, or to nothing
+    #[must_use]
+    pub fn new(data: Option<&Gc<T>>) -> Self {
+        // Ensure we don't create an atomic out of dead data...
+        if let Some(data) = data {
+            data.assert_live();
+        }
+
+        Self::new_unchecked(data)
+    }
+
+    /// Create a new `AtomicOptionGc`, without checking that `data` is live
+    ///
+    /// This is useful for data that is still being initialized by `Gc::new_cyclic`
+    pub(crate) fn new_unchecked(data: Option<&Gc<T>>) -> Self {
+        let atomic_ptr = Arc::new(AtomicPtr::new(raw_data_ptr(data)));
+
+        Self {
+            atomic_ptr: atomic_ptr.clone(),
+            backing_handle: COLLECTOR.new_handle_for_atomic(atomic_ptr),
+            _mark: PhantomData,
+        }
+    }
+
+    /// Create a new `AtomicOptionGc` that doesn't point to anything
+    #[must_use]
+    pub fn none() -> Self {
+        Self::new(None)
+    }
+
+    pub(crate) fn internal_handle(&self) -> InternalGcRef {
+        self.backing_handle.clone()
+    }
+
+    /// `load` the data from this `AtomicOptionGc<T>`, getting back a `Gc<T>` if there is any
+    ///
+    /// The ordering/atomicity guarantees are identical to `AtomicPtr::load`
+    #[must_use]
+    pub fn load(&self, ordering: Ordering) -> Option<Gc<T>> {
+        gc_from_data(COLLECTOR.load_atomic(&self.atomic_ptr, ordering))
+    }
+
+    /// `store` new data (or `None`) into this `AtomicOptionGc`
+    ///
+    /// The ordering/atomicity guarantees are identical to `AtomicPtr::store`
+    pub fn store(&self, v: Option<&Gc<T>>, ordering: Ordering) {
+        // Ensure we're not storing dead data...
+        if let Some(v) = v {
+            v.assert_live();
+        }
+
+        COLLECTOR.store_atomic(&self.atomic_ptr, raw_data_ptr(v), ordering);
+    }
+
+    /// `swap` what data is stored in this `AtomicOptionGc`, getting a `Gc` to the old data back
+    /// (if there was any)
+    ///
+    /// The ordering/atomicity guarantees are identical to `AtomicPtr::swap`
+    #[must_use]
+    pub fn swap(&self, v: Option<&Gc<T>>, ordering: Ordering) -> Option<Gc<T>> {
+        // Ensure we're not storing dead data...
+        if let Some(v) = v {
+            v.assert_live();
+        }
+
+        gc_from_data(COLLECTOR.swap_atomic(&self.atomic_ptr, raw_data_ptr(v), ordering))
+    }
+
+    /// `take` the data out of this `AtomicOptionGc`, leaving `None` in its place
+    ///
+    /// The ordering/atomicity guarantees are identical to `AtomicPtr::swap`
+    #[must_use]
+    pub fn take(&self, ordering: Ordering) -> Option<Gc<T>> {
+        self.swap(None, ordering)
+    }
+
+    /// Do a CAE operation. If this `AtomicOptionGc` points to the same data as `current` (or they
+    /// are both `None`) then after this operation it will point to the same data as `new`. (And
+    /// this happens atomically.)
+    ///
+    /// Data is compared for pointer equality. NOT `Eq` equality.
+    ///
+    /// The ordering/atomicity guarantees are identical to `AtomicPtr::compare_exchange`, refer to
+    /// that documentation for documentation about `success` and `failure` orderings.
+    ///
+    /// # Returns
+    /// Returns `true` if the swap happened and this `AtomicOptionGc` now points to `new`
+    /// Returns `false` if the swap failed / this `AtomicOptionGc` was not pointing to `current`
+    #[allow(clippy::must_use_candidate)]
+    pub fn compare_exchange(
+        &self,
+        current: Option<&Gc<T>>,
+        new: Option<&Gc<T>>,
+        success: Ordering,
+        failure: Ordering,
+    ) -> bool {
+        // Ensure we're not storing dead data...
+        if let Some(new) = new {
+            new.assert_live();
+        }
+
+        COLLECTOR.compare_exchange_atomic(
+            &self.atomic_ptr,
+            raw_data_ptr(current),
+            raw_data_ptr(new),
+            success,
+            failure,
+        )
+    }
+}
+
+impl<T: Scan> Default for AtomicOptionGc<T> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl<T: Scan> From<Option<&Gc<T>>> for AtomicOptionGc<T> {
+    fn from(data: Option<&Gc<T>>) -> Self {
+        Self::new(data)
+    }
+}
+
+unsafe impl<T: Scan> Scan for AtomicOptionGc<T> {
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        // The collector knows atomic handles may be null
+        scanner.add_internal_handle(self.internal_handle());
+    }
+}
+
+// The copy points to a copy of whatever this `AtomicOptionGc` points to when it is cloned
+impl<T: GcClone + GcDrop + 'static> GcClone for AtomicOptionGc<T> {
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        let current = self.load(Ordering::SeqCst);
+        let copy = current.map(|current| cloner.clone_gc(&current));
+        // The copy may still be under construction if we're in a cycle
+        Self::new_unchecked(copy.as_ref())
+    }
+}
+
+unsafe impl<T: Scan> GcSafe for AtomicOptionGc<T> {}
+// unsafe impl<T: Scan> !GcDrop for AtomicOptionGc<T> {}
+unsafe impl<T: Scan + Send + Sync> GcDeref for AtomicOptionGc<T> {}
+
+unsafe impl<T: Scan> Finalize for AtomicOptionGc<T> {
+    unsafe fn finalize(&mut self) {
+        drop_in_place(self)
+    }
+}
+
+impl<T: Scan> Drop for AtomicOptionGc<T> {
     fn drop(&mut self) {
         // Manually cleanup the backing handle...
         self.backing_handle.invalidate();
     }
 }
+
+/// Get the pointer to store in an atomic for `data` (null for `None`)
+fn raw_data_ptr<T: Scan>(data: Option<&Gc<T>>) -> *mut GcData {
+    data.map_or(ptr::null_mut(), |data| {
+        let data = data.internal_handle_ref().data();
+        // Reference counting can't see atomics, so leave this data for the collector
+        data.mark_in_atomic();
+        Arc::as_ptr(data).cast_mut()
+    })
+}
+
+/// Make a new `Gc` to data read out of an atomic (or `None` if the atomic was null)
+fn gc_from_data<T: Scan>(data: Option<Arc<GcData>>) -> Option<Gc<T>> {
+    data.map(|data| {
+        let ptr = data.scan_ptr().cast();
+        Gc::new_raw(COLLECTOR.handle_from_data(data), ptr)
+    })
+}
@@ -0,0 +1,249 @@
+use std::cell::{BorrowError, BorrowMutError, Ref, RefCell, RefMut};
+use std::fmt::{self, Debug, Formatter};
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use crate::collector::{GcGuardWarrant, COLLECTOR};
+use crate::marker::{GcDrop, GcSafe};
+use crate::{Finalize, GcClone, GcCloner, Scan, Scanner};
+
+/// A `RefCell` that tells the collector when it has been changed
+///
+/// This works just like a `RefCell`, except every mutation runs a write barrier once it's done
+/// (when a `GcCellRefMut` is dropped, or after `replace`, `swap` or `take`). The collector doesn't
+/// need to hear about writes yet, so the barrier does nothing for now. It's there so a collector
+/// that does need it (like an incremental or generational one) has a precise hook.
+///
+/// `Gc<GcCell<T>>` has specialized methods, so you don't need to call `get` first. They also tell the
+/// barrier exactly which allocation was written to, so prefer them where possible.
+///
+/// # Example
+/// ```
+/// use shredder::{Gc, GcCell};
+///
+/// let gc = Gc::new(GcCell::new(vec![1, 2]));
+/// gc.borrow_mut().push(3);
+///
+/// assert_eq!(gc.replace(Vec::new()), vec![1, 2, 3]);
+/// assert!(gc.borrow().is_empty());
+/// ```
+#[derive(Default)]
+pub struct GcCell<T: Scan> {
+    cell: RefCell<T>,
+}
+
+impl<T: Scan> GcCell<T> {
+    /// Create a new `GcCell` containing `value`
+    pub fn new(value: T) -> Self {
+        Self {
+            cell: RefCell::new(value),
+        }
+    }
+
+    /// Consume the `GcCell`, returning the value inside
+    pub fn into_inner(self) -> T {
+        self.cell.into_inner()
+    }
+
+    /// Immutably borrow the value, like `RefCell::borrow`
+    ///
+    /// # Panics
+    /// Panics if the value is currently mutably borrowed.
+    #[must_use]
+    pub fn borrow(&self) -> GcCellRef<'_, T> {
+        self.borrow_with(None)
+    }
+
+    /// Immutably borrow the value, like `RefCell::try_borrow`
+    ///
+    /// # Errors
+    /// Returns an error if the value is currently mutably borrowed.
+    pub fn try_borrow(&self) -> Result<GcCellRef<'_, T>, BorrowError> {
+        self.try_borrow_with(None)
+    }
+
+    /// Mutably borrow the value, like `RefCell::borrow_mut`
+    ///
+    /// The write barrier runs when the returned `GcCellRefMut` is dropped.
+    ///
+    /// # Panics
+    /// Panics if the value is currently borrowed.
+    #[must_use]
+    pub fn borrow_mut(&self) -> GcCellRefMut<'_, T> {
+        self.borrow_mut_with(None)
+    }
+
+    /// Mutably borrow the value, like `RefCell::try_borrow_mut`
+    ///
+    /// The write barrier runs when the returned `GcCellRefMut` is dropped.
+    ///
+    /// # Errors
+    /// Returns an error if the value is currently borrowed.
+    pub fn try_borrow_mut(&self) -> Result<GcCellRefMut<'_, T>, BorrowMutError> {
+        self.try_borrow_mut_with(None)
+    }
+
+    /// Replace the value, returning the old one, like `RefCell::replace`
+    ///
+    /// # Panics
+    /// Panics if the value is currently borrowed.
+    pub fn replace(&self, value: T) -> T {
+        mem::replace(&mut *self.borrow_mut(), value)
+    }
+
+    /// Swap the values of two `GcCell`s, like `RefCell::swap`
+    ///
+    /// # Panics
+    /// Panics if either value is currently borrowed.
+    pub fn swap(&self, other: &Self) {
+        mem::swap(&mut *self.borrow_mut(), &mut *other.borrow_mut());
+    }
+
+    /// Take the value, leaving `Default::default()` in its place, like `RefCell::take`
+    ///
+    /// # Panics
+    /// Panics if the value is currently borrowed.
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        self.replace(T::default())
+    }
+
+    /// Get a mutable reference to the value
+    ///
+    /// No barrier is needed here, since the `&mut` means the collector can't be looking at this
+    /// cell.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.cell.get_mut()
+    }
+
+    pub(crate) fn borrow_with(&self, warrant: Option<GcGuardWarrant>) -> GcCellRef<'_, T> {
+        GcCellRef {
+            cell_ref: self.cell.borrow(),
+            _warrant: warrant,
+        }
+    }
+
+    pub(crate) fn try_borrow_with(
+        &self,
+        warrant: Option<GcGuardWarrant>,
+    ) -> Result<GcCellRef<'_, T>, BorrowError> {
+        Ok(GcCellRef {
+            cell_ref: self.cell.try_borrow()?,
+            _warrant: warrant,
+        })
+    }
+
+    pub(crate) fn borrow_mut_with(&self, warrant: Option<GcGuardWarrant>) -> GcCellRefMut<'_, T> {
+        GcCellRefMut {
+            cell_ref: self.cell.borrow_mut(),
+            warrant,
+        }
+    }
+
+    pub(crate) fn try_borrow_mut_with(
+        &self,
+        warrant: Option<GcGuardWarrant>,
+    ) -> Result<GcCellRefMut<'_, T>, BorrowMutError> {
+        Ok(GcCellRefMut {
+            cell_ref: self.cell.try_borrow_mut()?,
+            warrant,
+        })
+    }
+}
+
+impl<T: Scan> From<T> for GcCell<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Scan + Debug> Debug for GcCell<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GcCell").field("value", &self.cell).finish()
+    }
+}
+
+// Like `RefCell`, a `GcCell` is never `GcDeref`
+unsafe impl<T: Scan + GcDrop> GcDrop for GcCell<T> {}
+unsafe impl<T: Scan + GcSafe> GcSafe for GcCell<T> {}
+
+unsafe impl<T: Scan> Scan for GcCell<T> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        self.cell.scan(scanner);
+    }
+}
+
+unsafe impl<T: Scan + Finalize> Finalize for GcCell<T> {
+    unsafe fn finalize(&mut self) {
+        self.cell.finalize();
+    }
+}
+
+impl<T: GcClone> GcClone for GcCell<T> {
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        Self {
+            cell: self.cell.gc_clone(cloner),
+        }
+    }
+}
+
+/// This is like a `Ref`, but for a `GcCell`
+pub struct GcCellRef<'a, T: Scan> {
+    cell_ref: Ref<'a, T>,
+    // Declared after `cell_ref`, so it is released after the borrow is
+    _warrant: Option<GcGuardWarrant>,
+}
+
+impl<T: Scan> Deref for GcCellRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.cell_ref
+    }
+}
+
+impl<T: Scan + Debug> Debug for GcCellRef<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GcCellRef")
+            .field("ref", self.deref())
+            .finish()
+    }
+}
+
+/// This is like a `RefMut`, but for a `GcCell`. Dropping it runs the write barrier.
+pub struct GcCellRefMut<'a, T: Scan> {
+    cell_ref: RefMut<'a, T>,
+    // Declared after `cell_ref`, so it is released after the borrow is
+    warrant: Option<GcGuardWarrant>,
+}
+
+impl<T: Scan> Deref for GcCellRefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.cell_ref
+    }
+}
+
+impl<T: Scan> DerefMut for GcCellRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.cell_ref
+    }
+}
+
+impl<T: Scan + Debug> Debug for GcCellRefMut<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GcCellRefMut")
+            .field("ref", self.deref())
+            .finish()
+    }
+}
+
+impl<T: Scan> Drop for GcCellRefMut<'_, T> {
+    fn drop(&mut self) {
+        COLLECTOR.write_barrier(self.warrant.as_ref());
+    }
+}
@@ -0,0 +1,23 @@
+//! The concurrent structures (`Stack`, `Queue` and `SkipListMap`) are built on compare-and-swap
+//! rather than locks, so threads never wait on each other to make progress. Since the collector owns
+//! all the nodes, there are no ABA problems or memory reclamation schemes to worry about. They hold
+//! `Gc<T>`s, so values can be handed out without copying them.
+//!
+//! They aren't lock-free though, since looking at a node goes through `Gc::get`. That waits while a
+//! collection is marking, like any other `Gc::get` does.
+//!
+//! The persistent structures (`PersistentVec` and `PersistentHashMap`) are immutable. "Modifying"
+//! one gives you a new version, which shares most of its structure with the old one. Since every
+//! version is just a couple of `Gc`s, they're cheap to clone and to share between threads.
+
+mod persistent_map;
+mod persistent_vec;
+mod queue;
+mod skip_list;
+mod stack;
+
+pub use persistent_map::{PersistentHashMap, PersistentHashMapIter};
+pub use persistent_vec::{PersistentVec, PersistentVecIter};
+pub use queue::Queue;
+pub use skip_list::{SkipListMap, SkipListMapIter};
+pub use stack::Stack;
@@ -0,0 +1,543 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+use std::ptr::drop_in_place;
+
+use crate::marker::{GcDeref, GcDrop, GcSafe};
+use crate::{Finalize, Gc, Scan, Scanner};
+
+const BITS: u32 = 5;
+const MASK: u64 = (1 << BITS) - 1;
+
+/// A persistent (immutable) hash map, with structural sharing between versions
+///
+/// "Modifying" a `PersistentHashMap` returns a new version, leaving the old one untouched. Each new
+/// version only allocates the handful of nodes on the path to the change; everything else is shared.
+/// (Internally this is a hash array mapped trie.)
+///
+/// Since the map is shared, values are cloned on the way out. Store `Gc`s in it if your values
+/// are expensive to clone.
+///
+/// # Example
+/// ```
+/// use shredder::collections::PersistentHashMap;
+///
+/// let m1 = PersistentHashMap::new().insert(String::from("a"), 1);
+/// let m2 = m1.insert(String::from("b"), 2);
+/// let m3 = m2.remove("a");
+///
+/// assert_eq!(m1.get("a"), Some(1));
+/// assert_eq!(m1.get("b"), None);
+/// assert_eq!(m2.len(), 2);
+/// assert_eq!(m3.get("a"), None);
+/// assert_eq!(m3.get("b"), Some(2));
+/// ```
+pub struct PersistentHashMap<K: Scan, V: Scan> {
+    root: Option<Gc<HamtNode<K, V>>>,
+    len: usize,
+    hasher: RandomState,
+}
+
+enum HamtNode<K: Scan, V: Scan> {
+    // Each set bit in `bitmap` is a slot that's in use, and `children` holds them in order
+    Branch {
+        bitmap: u32,
+        children: Vec<HamtChild<K, V>>,
+    },
+    // Once we run out of hash bits, keys with the same hash all end up in one of these
+    Collision {
+        entries: Vec<(K, V)>,
+    },
+}
+
+enum HamtChild<K: Scan, V: Scan> {
+    Entry { hash: u64, key: K, value: V },
+    Node(Gc<HamtNode<K, V>>),
+}
+
+/// What happened when we tried to remove something from a node
+enum Removal<K: Scan, V: Scan> {
+    NotFound,
+    // The node is now empty
+    Emptied,
+    // The node just holds a single entry, which can be pulled up into the parent
+    Single(HamtChild<K, V>),
+    Replaced(Gc<HamtNode<K, V>>),
+}
+
+/// Which bit of a `Branch`'s bitmap is the slot for `hash` at `shift`?
+#[allow(clippy::cast_possible_truncation)]
+fn bit_of(hash: u64, shift: u32) -> u32 {
+    1 << ((hash >> shift) & MASK) as u32
+}
+
+/// Where in a `Branch`'s `children` is the slot for `bit`?
+fn index_of(bitmap: u32, bit: u32) -> usize {
+    (bitmap & (bit - 1)).count_ones() as usize
+}
+
+impl<K, V> Clone for HamtChild<K, V>
+where
+    K: Scan + Clone,
+    V: Scan + Clone,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Self::Entry { hash, key, value } => Self::Entry {
+                hash: *hash,
+                key: key.clone(),
+                value: value.clone(),
+            },
+            Self::Node(node) => Self::Node(node.clone()),
+        }
+    }
+}
+
+impl<K, V> HamtNode<K, V>
+where
+    K: Scan + GcDrop + Clone + Eq,
+    V: Scan + GcDrop + Clone,
+{
+    /// A node holding two entries with different keys
+    fn pair(shift: u32, a: (u64, K, V), b: (u64, K, V)) -> Gc<Self> {
+        if shift >= u64::BITS {
+            return Gc::new(Self::Collision {
+                entries: vec![(a.1, a.2), (b.1, b.2)],
+            });
+        }
+
+        let a_bit = bit_of(a.0, shift);
+        let b_bit = bit_of(b.0, shift);
+        let node = if a_bit == b_bit {
+            Self::Branch {
+                bitmap: a_bit,
+                children: vec![HamtChild::Node(Self::pair(shift + BITS, a, b))],
+            }
+        } else {
+            let a = HamtChild::Entry {
+                hash: a.0,
+                key: a.1,
+                value: a.2,
+            };
+            let b = HamtChild::Entry {
+                hash: b.0,
+                key: b.1,
+                value: b.2,
+            };
+            let children = if a_bit < b_bit {
+                vec![a, b]
+            } else {
+                vec![b, a]
+            };
+            Self::Branch {
+                bitmap: a_bit | b_bit,
+                children,
+            }
+        };
+        Gc::new(node)
+    }
+
+    /// A copy of `node` with `key` mapped to `value`. Also returns whether `key` is new
+    fn insert(node: &Gc<Self>, shift: u32, hash: u64, key: K, value: V) -> (Gc<Self>, bool) {
+        let (new_node, added) = match &*node.get() {
+            Self::Collision { entries } => {
+                let mut entries = entries.clone();
+                let added = if let Some(entry) = entries.iter_mut().find(|(k, _)| *k == key) {
+                    entry.1 = value;
+                    false
+                } else {
+                    entries.push((key, value));
+                    true
+                };
+                (Self::Collision { entries }, added)
+            }
+            Self::Branch { bitmap, children } => {
+                let bit = bit_of(hash, shift);
+                let index = index_of(*bitmap, bit);
+                let mut children = children.clone();
+
+                let added = if bitmap & bit == 0 {
+                    children.insert(index, HamtChild::Entry { hash, key, value });
+                    true
+                } else {
+                    let (new_child, added) = match &children[index] {
+                        HamtChild::Entry {
+                            hash: old_hash,
+                            key: old_key,
+                            value: old_value,
+                        } => {
+                            if *old_key == key {
+                                (HamtChild::Entry { hash, key, value }, false)
+                            } else {
+                                let old = (*old_hash, old_key.clone(), old_value.clone());
+                                let new_node = Self::pair(shift + BITS, old, (hash, key, value));
+                                (HamtChild::Node(new_node), true)
+                            }
+                        }
+                        HamtChild::Node(child) => {
+                            let (new_child, added) =
+                                Self::insert(child, shift + BITS, hash, key, value);
+                            (HamtChild::Node(new_child), added)
+                        }
+                    };
+                    children[index] = new_child;
+                    added
+                };
+
+                (
+                    Self::Branch {
+                        bitmap: bitmap | bit,
+                        children,
+                    },
+                    added,
+                )
+            }
+        };
+        (Gc::new(new_node), added)
+    }
+
+    /// Try to remove `key` from (a copy of) `node`
+    fn remove<Q>(node: &Gc<Self>, shift: u32, hash: u64, key: &Q) -> Removal<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        match &*node.get() {
+            Self::Collision { entries } => {
+                let Some(position) = entries.iter().position(|(k, _)| k.borrow() == key) else {
+                    return Removal::NotFound;
+                };
+                let mut entries = entries.clone();
+                entries.remove(position);
+
+                if entries.len() == 1 {
+                    let (key, value) = entries.remove(0);
+                    Removal::Single(HamtChild::Entry { hash, key, value })
+                } else {
+                    Removal::Replaced(Gc::new(Self::Collision { entries }))
+                }
+            }
+            Self::Branch { bitmap, children } => {
+                let bit = bit_of(hash, shift);
+                if bitmap & bit == 0 {
+                    return Removal::NotFound;
+                }
+                let index = index_of(*bitmap, bit);
+
+                let new_child = match &children[index] {
+                    HamtChild::Entry { key: old_key, .. } => {
+                        if old_key.borrow() != key {
+                            return Removal::NotFound;
+                        }
+                        None
+                    }
+                    HamtChild::Node(child) => match Self::remove(child, shift + BITS, hash, key) {
+                        Removal::NotFound => return Removal::NotFound,
+                        Removal::Emptied => None,
+                        Removal::Single(entry) => Some(entry),
+                        Removal::Replaced(new_child) => Some(HamtChild::Node(new_child)),
+                    },
+                };
+
+                let mut children = children.clone();
+                let bitmap = if let Some(new_child) = new_child {
+                    children[index] = new_child;
+                    *bitmap
+                } else {
+                    children.remove(index);
+                    bitmap & !bit
+                };
+
+                match children.as_slice() {
+                    [] => Removal::Emptied,
+                    // A lone entry can move up a level (but not past the root, see `PersistentHashMap::remove`)
+                    [HamtChild::Entry { .. }] => Removal::Single(children.remove(0)),
+                    _ => Removal::Replaced(Gc::new(Self::Branch { bitmap, children })),
+                }
+            }
+        }
+    }
+}
+
+impl<K, V> PersistentHashMap<K, V>
+where
+    K: Scan + GcDrop + Clone + Eq + Hash,
+    V: Scan + GcDrop + Clone,
+{
+    /// Create a new, empty `PersistentHashMap`
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            len: 0,
+            hasher: RandomState::new(),
+        }
+    }
+
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        self.hasher.hash_one(key)
+    }
+
+    /// How many entries are in this map
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Is this map empty?
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get (a clone of) the value for `key`, if there is one
+    #[must_use]
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let hash = self.hash(key);
+        let mut node = self.root.clone()?;
+        let mut shift = 0;
+        loop {
+            let child = match &*node.get() {
+                HamtNode::Collision { entries } => {
+                    return entries
+                        .iter()
+                        .find(|(k, _)| k.borrow() == key)
+                        .map(|(_, v)| v.clone());
+                }
+                HamtNode::Branch { bitmap, children } => {
+                    let bit = bit_of(hash, shift);
+                    if bitmap & bit == 0 {
+                        return None;
+                    }
+                    match &children[index_of(*bitmap, bit)] {
+                        HamtChild::Entry {
+                            key: k, value: v, ..
+                        } => {
+                            return if k.borrow() == key {
+                                Some(v.clone())
+                            } else {
+                                None
+                            };
+                        }
+                        HamtChild::Node(child) => child.clone(),
+                    }
+                }
+            };
+            node = child;
+            shift += BITS;
+        }
+    }
+
+    /// Is there an entry for `key`?
+    #[must_use]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// A new version of this map, with `key` mapped to `value`
+    #[must_use]
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let hash = self.hash(&key);
+        let (root, added) = if let Some(root) = &self.root {
+            HamtNode::insert(root, 0, hash, key, value)
+        } else {
+            let bit = bit_of(hash, 0);
+            let root = HamtNode::Branch {
+                bitmap: bit,
+                children: vec![HamtChild::Entry { hash, key, value }],
+            };
+            (Gc::new(root), true)
+        };
+
+        Self {
+            root: Some(root),
+            len: if added { self.len + 1 } else { self.len },
+            hasher: self.hasher.clone(),
+        }
+    }
+
+    /// A new version of this map, without an entry for `key`
+    #[must_use]
+    pub fn remove<Q>(&self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let Some(root) = &self.root else {
+            return self.clone();
+        };
+
+        let root = match HamtNode::remove(root, 0, self.hash(key), key) {
+            Removal::NotFound => return self.clone(),
+            Removal::Emptied => None,
+            // The root is always a branch, so put the lone entry back in one
+            Removal::Single(entry) => {
+                let hash = match &entry {
+                    HamtChild::Entry { hash, .. } => *hash,
+                    HamtChild::Node(_) => unreachable!("only entries are pulled up"),
+                };
+                let bit = bit_of(hash, 0);
+                Some(Gc::new(HamtNode::Branch {
+                    bitmap: bit,
+                    children: vec![entry],
+                }))
+            }
+            Removal::Replaced(root) => Some(root),
+        };
+
+        Self {
+            root,
+            len: self.len - 1,
+            hasher: self.hasher.clone(),
+        }
+    }
+
+    /// Iterate over (clones of) the entries in this map, in no particular order
+    #[must_use]
+    pub fn iter(&self) -> PersistentHashMapIter<K, V> {
+        PersistentHashMapIter {
+            nodes: self.root.iter().cloned().collect(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<K, V> Default for PersistentHashMap<K, V>
+where
+    K: Scan + GcDrop + Clone + Eq + Hash,
+    V: Scan + GcDrop + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Cloning is cheap, since everything is shared
+impl<K: Scan, V: Scan> Clone for PersistentHashMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for PersistentHashMap<K, V>
+where
+    K: Scan + GcDrop + Clone + Eq + Hash,
+    V: Scan + GcDrop + Clone,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::new(), |map, (key, value)| map.insert(key, value))
+    }
+}
+
+impl<K, V> Debug for PersistentHashMap<K, V>
+where
+    K: Scan + GcDrop + Clone + Eq + Hash + Debug,
+    V: Scan + GcDrop + Clone + Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> IntoIterator for &PersistentHashMap<K, V>
+where
+    K: Scan + GcDrop + Clone + Eq + Hash,
+    V: Scan + GcDrop + Clone,
+{
+    type Item = (K, V);
+    type IntoIter = PersistentHashMapIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+unsafe impl<K: Scan, V: Scan> Scan for PersistentHashMap<K, V> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        scanner.scan(&self.root);
+    }
+}
+
+unsafe impl<K: Scan, V: Scan> GcSafe for PersistentHashMap<K, V> {}
+unsafe impl<K: Scan, V: Scan> GcDrop for PersistentHashMap<K, V> {}
+unsafe impl<K: Scan + Send + Sync, V: Scan + Send + Sync> GcDeref for PersistentHashMap<K, V> {}
+
+unsafe impl<K: Scan, V: Scan> Finalize for PersistentHashMap<K, V> {
+    unsafe fn finalize(&mut self) {
+        drop_in_place(self)
+    }
+}
+
+unsafe impl<K: Scan, V: Scan> Scan for HamtNode<K, V> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        match self {
+            Self::Branch { children, .. } => {
+                for child in children {
+                    match child {
+                        HamtChild::Entry { key, value, .. } => {
+                            scanner.scan(key);
+                            scanner.scan(value);
+                        }
+                        HamtChild::Node(node) => scanner.scan(node),
+                    }
+                }
+            }
+            Self::Collision { entries } => scanner.scan(entries),
+        }
+    }
+}
+
+unsafe impl<K: Scan, V: Scan> GcSafe for HamtNode<K, V> {}
+unsafe impl<K: Scan + GcDrop, V: Scan + GcDrop> GcDrop for HamtNode<K, V> {}
+
+/// An iterator over the entries of a `PersistentHashMap`, see `PersistentHashMap::iter`
+pub struct PersistentHashMapIter<K: Scan, V: Scan> {
+    // Nodes we haven't looked in yet
+    nodes: Vec<Gc<HamtNode<K, V>>>,
+    // Entries from the last node we looked in
+    pending: Vec<(K, V)>,
+}
+
+impl<K: Scan + Clone, V: Scan + Clone> Iterator for PersistentHashMapIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.pending.pop() {
+                return Some(entry);
+            }
+
+            let node = self.nodes.pop()?;
+            match &*node.get() {
+                HamtNode::Branch { children, .. } => {
+                    for child in children {
+                        match child {
+                            HamtChild::Entry { key, value, .. } => {
+                                self.pending.push((key.clone(), value.clone()));
+                            }
+                            HamtChild::Node(child) => self.nodes.push(child.clone()),
+                        }
+                    }
+                }
+                HamtNode::Collision { entries } => self.pending.extend(entries.iter().cloned()),
+            }
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use std::fmt::{self, Debug, Formatter};
+use std::iter::FromIterator;
+use std::ptr::drop_in_place;
+
+use crate::marker::{GcDeref, GcDrop, GcSafe};
+use crate::{Finalize, Gc, Scan, Scanner};
+
+const BITS: u32 = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+/// A persistent (immutable) vector, with structural sharing between versions
+///
+/// "Modifying" a `PersistentVec` returns a new version, leaving the old one untouched. Each new
+/// version only allocates the handful of nodes on the path to the change; everything else is shared.
+/// (Internally this is a 32-way trie, so operations are `O(log32 n)`, which is effectively constant.)
+///
+/// Since the vector is shared, values are cloned on the way out. Store `Gc`s in it if your values
+/// are expensive to clone.
+///
+/// # Example
+/// ```
+/// use shredder::collections::PersistentVec;
+///
+/// let v1: PersistentVec<u32> = (0..3).collect();
+/// let v2 = v1.push(3);
+/// let v3 = v2.set(0, 100).unwrap();
+///
+/// assert_eq!(v1.iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+/// assert_eq!(v2.iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+/// assert_eq!(v3.get(0), Some(100));
+/// assert_eq!(v3.drop_last().unwrap().len(), 3);
+/// ```
+pub struct PersistentVec<T: Scan> {
+    root: Option<Gc<PvNode<T>>>,
+    len: usize,
+    // How far to shift an index to find its slot in the root. (Zero if the root is a leaf.)
+    shift: u32,
+}
+
+enum PvNode<T: Scan> {
+    Branch(Vec<Gc<Self>>),
+    Leaf(Vec<T>),
+}
+
+impl<T: Scan + GcDrop + Clone> PvNode<T> {
+    /// A new chain of nodes down to a leaf containing just `value`
+    fn new_path(shift: u32, value: T) -> Gc<Self> {
+        if shift == 0 {
+            Gc::new(Self::Leaf(vec![value]))
+        } else {
+            Gc::new(Self::Branch(vec![Self::new_path(shift - BITS, value)]))
+        }
+    }
+
+    /// A copy of `node`, with `value` added at `index` (which must be the end of the vector)
+    fn push(node: &Gc<Self>, shift: u32, index: usize, value: T) -> Gc<Self> {
+        let new_node = match &*node.get() {
+            Self::Leaf(values) => {
+                let mut values = values.clone();
+                values.push(value);
+                Self::Leaf(values)
+            }
+            Self::Branch(children) => {
+                let mut children = children.clone();
+                let slot = (index >> shift) & MASK;
+                if let Some(child) = children.get_mut(slot) {
+                    *child = Self::push(child, shift - BITS, index, value);
+                } else {
+                    children.push(Self::new_path(shift - BITS, value));
+                }
+                Self::Branch(children)
+            }
+        };
+        Gc::new(new_node)
+    }
+
+    /// A copy of `node`, with the value at `index` replaced by `value`
+    fn set(node: &Gc<Self>, shift: u32, index: usize, value: T) -> Gc<Self> {
+        let new_node = match &*node.get() {
+            Self::Leaf(values) => {
+                let mut values = values.clone();
+                values[index & MASK] = value;
+                Self::Leaf(values)
+            }
+            Self::Branch(children) => {
+                let mut children = children.clone();
+                let slot = (index >> shift) & MASK;
+                children[slot] = Self::set(&children[slot], shift - BITS, index, value);
+                Self::Branch(children)
+            }
+        };
+        Gc::new(new_node)
+    }
+
+    /// A copy of `node`, without the value at `index` (which must be the last value)
+    ///
+    /// Returns `None` if the copy would be empty.
+    fn drop_last(node: &Gc<Self>, shift: u32, index: usize) -> Option<Gc<Self>> {
+        let new_node = match &*node.get() {
+            Self::Leaf(values) => {
+                let values = values[..values.len() - 1].to_vec();
+                if values.is_empty() {
+                    return None;
+                }
+                Self::Leaf(values)
+            }
+            Self::Branch(children) => {
+                let mut children = children.clone();
+                let slot = (index >> shift) & MASK;
+                if let Some(child) = Self::drop_last(&children[slot], shift - BITS, index) {
+                    children[slot] = child;
+                } else {
+                    children.truncate(slot);
+                    if children.is_empty() {
+                        return None;
+                    }
+                }
+                Self::Branch(children)
+            }
+        };
+        Some(Gc::new(new_node))
+    }
+}
+
+impl<T: Scan + GcDrop + Clone> PersistentVec<T> {
+    /// Create a new, empty `PersistentVec`
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            len: 0,
+            shift: 0,
+        }
+    }
+
+    /// How many values are in this vector
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Is this vector empty?
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get (a clone of) the value at `index`, if `index` is in bounds
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+
+        let mut node = self.root.clone()?;
+        let mut shift = self.shift;
+        loop {
+            let child = match &*node.get() {
+                PvNode::Leaf(values) => return Some(values[index & MASK].clone()),
+                PvNode::Branch(children) => children[(index >> shift) & MASK].clone(),
+            };
+            node = child;
+            shift -= BITS;
+        }
+    }
+
+    /// Get (a clone of) the last value, if there is one
+    #[must_use]
+    pub fn last(&self) -> Option<T> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    /// A new version of this vector, with `value` added to the end
+    #[must_use]
+    pub fn push(&self, value: T) -> Self {
+        let Some(root) = &self.root else {
+            return Self {
+                root: Some(PvNode::new_path(0, value)),
+                len: 1,
+                shift: 0,
+            };
+        };
+
+        // If the trie is full, we need to add a level on top
+        let (new_root, shift) = if self.len == WIDTH << self.shift {
+            let new_root = PvNode::Branch(vec![root.clone(), PvNode::new_path(self.shift, value)]);
+            (Gc::new(new_root), self.shift + BITS)
+        } else {
+            (PvNode::push(root, self.shift, self.len, value), self.shift)
+        };
+
+        Self {
+            root: Some(new_root),
+            len: self.len + 1,
+            shift,
+        }
+    }
+
+    /// A new version of this vector, with the value at `index` replaced by `value`
+    ///
+    /// Returns `None` if `index` is out of bounds.
+    #[must_use]
+    pub fn set(&self, index: usize, value: T) -> Option<Self> {
+        if index >= self.len {
+            return None;
+        }
+
+        let root = self.root.as_ref()?;
+        Some(Self {
+            root: Some(PvNode::set(root, self.shift, index, value)),
+            len: self.len,
+            shift: self.shift,
+        })
+    }
+
+    /// A new version of this vector, without the last value
+    ///
+    /// Returns `None` if this vector is empty.
+    #[must_use]
+    pub fn drop_last(&self) -> Option<Self> {
+        let root = self.root.as_ref()?;
+        let mut new_root = PvNode::drop_last(root, self.shift, self.len - 1);
+        let mut shift = self.shift;
+
+        // If the root only has one child left, that child can be the root
+        while let Some(root) = new_root.clone() {
+            let only_child = match &*root.get() {
+                PvNode::Branch(children) if children.len() == 1 => children[0].clone(),
+                _ => break,
+            };
+            new_root = Some(only_child);
+            shift -= BITS;
+        }
+
+        Some(Self {
+            root: new_root,
+            len: self.len - 1,
+            shift,
+        })
+    }
+
+    /// Iterate over (clones of) the values in this vector
+    #[must_use]
+    pub fn iter(&self) -> PersistentVecIter<T> {
+        PersistentVecIter {
+            vec: self.clone(),
+            index: 0,
+        }
+    }
+}
+
+impl<T: Scan + GcDrop + Clone> Default for PersistentVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Cloning is cheap, since everything is shared
+impl<T: Scan> Clone for PersistentVec<T> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+            shift: self.shift,
+        }
+    }
+}
+
+impl<T: Scan + GcDrop + Clone> FromIterator<T> for PersistentVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::new(), |vec, value| vec.push(value))
+    }
+}
+
+impl<T: Scan + GcDrop + Clone + PartialEq> PartialEq for PersistentVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Scan + GcDrop + Clone + Eq> Eq for PersistentVec<T> {}
+
+impl<T: Scan + GcDrop + Clone + Debug> Debug for PersistentVec<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Scan + GcDrop + Clone> IntoIterator for &PersistentVec<T> {
+    type Item = T;
+    type IntoIter = PersistentVecIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+unsafe impl<T: Scan> Scan for PersistentVec<T> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        scanner.scan(&self.root);
+    }
+}
+
+unsafe impl<T: Scan> GcSafe for PersistentVec<T> {}
+unsafe impl<T: Scan> GcDrop for PersistentVec<T> {}
+unsafe impl<T: Scan + Send + Sync> GcDeref for PersistentVec<T> {}
+
+unsafe impl<T: Scan> Finalize for PersistentVec<T> {
+    unsafe fn finalize(&mut self) {
+        drop_in_place(self)
+    }
+}
+
+unsafe impl<T: Scan> Scan for PvNode<T> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        match self {
+            Self::Branch(children) => scanner.scan(children),
+            Self::Leaf(values) => scanner.scan(values),
+        }
+    }
+}
+
+unsafe impl<T: Scan> GcSafe for PvNode<T> {}
+unsafe impl<T: Scan + GcDrop> GcDrop for PvNode<T> {}
+
+/// An iterator over the values of a `PersistentVec`, see `PersistentVec::iter`
+pub struct PersistentVecIter<T: Scan> {
+    vec: PersistentVec<T>,
+    index: usize,
+}
+
+impl<T: Scan + GcDrop + Clone> Iterator for PersistentVecIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.vec.get(self.index)?;
+        self.index += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.vec.len() - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Scan + GcDrop + Clone> ExactSizeIterator for PersistentVecIter<T> {}
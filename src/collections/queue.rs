@@ -0,0 +1,158 @@
+use std::fmt::{self, Debug, Formatter};
+use std::ptr::drop_in_place;
+use std::sync::atomic::Ordering;
+
+use crate::atomic::{AtomicGc, AtomicOptionGc};
+use crate::marker::{GcDeref, GcSafe};
+use crate::{Finalize, Gc, Scan, Scanner};
+
+/// A compare-and-swap based, multi-producer multi-consumer FIFO queue of `Gc<T>`s (a Michael-Scott
+/// queue)
+///
+/// # Example
+/// ```
+/// use shredder::collections::Queue;
+/// use shredder::Gc;
+///
+/// let queue = Queue::new();
+/// queue.push(&Gc::new(1));
+/// queue.push(&Gc::new(2));
+///
+/// assert_eq!(*queue.pop().unwrap().get(), 1);
+/// assert_eq!(*queue.pop().unwrap().get(), 2);
+/// assert!(queue.pop().is_none());
+/// ```
+pub struct Queue<T: Scan> {
+    // `head` always points to a sentinel node, the first value is in the node after it
+    head: AtomicGc<QueueNode<T>>,
+    // `tail` points to the last node, or a node shortly before it
+    tail: AtomicGc<QueueNode<T>>,
+}
+
+struct QueueNode<T: Scan> {
+    // Taken out by whoever makes this node the new sentinel, so the queue doesn't keep it alive
+    value: AtomicOptionGc<T>,
+    next: AtomicOptionGc<Self>,
+}
+
+impl<T: Scan> QueueNode<T> {
+    fn new(value: Option<&Gc<T>>) -> Gc<Self> {
+        Gc::new_with_finalizer(Self {
+            value: AtomicOptionGc::new(value),
+            next: AtomicOptionGc::none(),
+        })
+    }
+}
+
+impl<T: Scan> Queue<T> {
+    /// Create a new, empty `Queue`
+    #[must_use]
+    pub fn new() -> Self {
+        let sentinel = QueueNode::new(None);
+
+        Self {
+            head: AtomicGc::new(&sentinel),
+            tail: AtomicGc::new(&sentinel),
+        }
+    }
+
+    /// Push `value` onto the back of the queue
+    pub fn push(&self, value: &Gc<T>) {
+        let node = QueueNode::new(Some(value));
+
+        loop {
+            let tail = self.tail.load(Ordering::Acquire);
+            let next = tail.get().next.load(Ordering::Acquire);
+
+            if let Some(next) = next {
+                // `tail` has fallen behind, help move it along before trying again
+                self.tail
+                    .compare_exchange(&tail, &next, Ordering::AcqRel, Ordering::Acquire);
+            } else if tail.get().next.compare_exchange(
+                None,
+                Some(&node),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                // It's fine if this fails, it means someone else already helped
+                self.tail
+                    .compare_exchange(&tail, &node, Ordering::AcqRel, Ordering::Acquire);
+                return;
+            }
+        }
+    }
+
+    /// Pop the value at the front of the queue, if there is one
+    #[must_use]
+    pub fn pop(&self) -> Option<Gc<T>> {
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            let next = head.get().next.load(Ordering::Acquire)?;
+
+            if self
+                .head
+                .compare_exchange(&head, &next, Ordering::AcqRel, Ordering::Acquire)
+            {
+                // `next` is the new sentinel, and only we could have made it so. Its value is ours
+                let value = next.get().value.take(Ordering::AcqRel);
+                return Some(value.unwrap_or_else(|| unreachable!("queue nodes hold a value")));
+            }
+        }
+    }
+
+    /// Is the queue empty right now?
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        let head = self.head.load(Ordering::Acquire);
+        let next = head.get().next.load(Ordering::Acquire);
+        next.is_none()
+    }
+}
+
+impl<T: Scan> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Scan> Debug for Queue<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Queue")
+            .field("is_empty", &self.is_empty())
+            .finish()
+    }
+}
+
+unsafe impl<T: Scan> Scan for Queue<T> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        scanner.scan(&self.head);
+        scanner.scan(&self.tail);
+    }
+}
+
+unsafe impl<T: Scan> GcSafe for Queue<T> {}
+// unsafe impl<T: Scan> !GcDrop for Queue<T> {}
+unsafe impl<T: Scan + Send + Sync> GcDeref for Queue<T> {}
+
+unsafe impl<T: Scan> Finalize for Queue<T> {
+    unsafe fn finalize(&mut self) {
+        drop_in_place(self)
+    }
+}
+
+unsafe impl<T: Scan> Scan for QueueNode<T> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        scanner.scan(&self.value);
+        scanner.scan(&self.next);
+    }
+}
+
+unsafe impl<T: Scan> GcSafe for QueueNode<T> {}
+
+unsafe impl<T: Scan> Finalize for QueueNode<T> {
+    unsafe fn finalize(&mut self) {
+        drop_in_place(self)
+    }
+}
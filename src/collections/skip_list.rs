@@ -0,0 +1,494 @@
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::hash_map::RandomState;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{BuildHasher, Hasher};
+use std::ptr::drop_in_place;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::atomic::AtomicOptionGc;
+use crate::marker::{GcDeref, GcDrop, GcSafe};
+use crate::{Finalize, Gc, Scan, Scanner};
+
+/// The most levels a node can be part of. (Enough for billions of entries.)
+const MAX_HEIGHT: usize = 32;
+
+/// A compare-and-swap based, sorted map from `K` to `Gc<V>` (a skip list)
+///
+/// Entries can be inserted and removed, but not changed in place. If you want to change values,
+/// store something like a `Gc<GcCell<V>>` or `Gc<Mutex<V>>`.
+///
+/// # Example
+/// ```
+/// use shredder::collections::SkipListMap;
+/// use shredder::Gc;
+///
+/// let map = SkipListMap::new();
+/// assert!(map.insert(2, Gc::new('2')));
+/// assert!(map.insert(1, Gc::new('1')));
+/// assert!(!map.insert(1, Gc::new('I')));
+///
+/// assert_eq!(*map.get(&1).unwrap().get(), '1');
+/// assert_eq!(map.iter().map(|(k, _)| k).collect::<Vec<_>>(), vec![1, 2]);
+///
+/// assert_eq!(*map.remove(&2).unwrap().get(), '2');
+/// assert!(!map.contains_key(&2));
+/// ```
+pub struct SkipListMap<K: Scan + GcDrop, V: Scan + ?Sized> {
+    head: Gc<SkipNode<K, V>>,
+    len: AtomicUsize,
+}
+
+/// Removed nodes are marked by pointing them at a marker node (on every level they're part of),
+/// which sits between them and their old successor. Anyone trying to insert after a removed node
+/// will fail to CAS its `next`, and anyone passing it will help unlink it.
+struct SkipNode<K: Scan + GcDrop, V: Scan + ?Sized> {
+    // `None` for the head and for markers. (Nothing points to the head, so if you reach a node
+    // with no entry by following a `next`, it's a marker.)
+    entry: Option<(K, Gc<V>)>,
+    // Markers only ever have one level
+    next: Vec<AtomicOptionGc<Self>>,
+}
+
+impl<K: Scan + GcDrop, V: Scan + ?Sized> SkipNode<K, V> {
+    fn is_marker(&self) -> bool {
+        self.entry.is_none()
+    }
+
+    fn key(&self) -> &K {
+        let (key, _) = self
+            .entry
+            .as_ref()
+            .expect("only markers and the head have no key");
+        key
+    }
+
+    fn value(&self) -> &Gc<V> {
+        let (_, value) = self
+            .entry
+            .as_ref()
+            .expect("only markers and the head have no value");
+        value
+    }
+
+    fn new_marker(succ: Option<&Gc<Self>>) -> Gc<Self> {
+        Gc::new_with_finalizer(Self {
+            entry: None,
+            next: vec![AtomicOptionGc::new(succ)],
+        })
+    }
+}
+
+/// Is this node (the target of some `next`) a marker?
+fn is_marker<K: Scan + GcDrop, V: Scan + ?Sized>(node: Option<&Gc<SkipNode<K, V>>>) -> bool {
+    node.is_some_and(|node| node.get().is_marker())
+}
+
+/// Mark `next`, so the node it belongs to is removed on that level
+///
+/// Returns `false` if it had already been marked (by someone else)
+fn mark<K: Scan + GcDrop, V: Scan + ?Sized>(next: &AtomicOptionGc<SkipNode<K, V>>) -> bool {
+    loop {
+        let succ = next.load(Ordering::Acquire);
+        if is_marker(succ.as_ref()) {
+            return false;
+        }
+
+        let marker = SkipNode::new_marker(succ.as_ref());
+        if next.compare_exchange(
+            succ.as_ref(),
+            Some(&marker),
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            return true;
+        }
+    }
+}
+
+/// Pick a height for a new node, where each extra level is half as likely as the last
+#[allow(clippy::cast_possible_truncation)]
+fn random_height() -> usize {
+    thread_local! {
+        // `RandomState` is the easiest source of randomness in `std`. Zero would get us stuck
+        static RNG_STATE: Cell<u32> = Cell::new(RandomState::new().build_hasher().finish() as u32 | 1);
+    }
+
+    RNG_STATE.with(|state| {
+        // xorshift32
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        state.set(x);
+
+        (x.trailing_ones() as usize + 1).min(MAX_HEIGHT)
+    })
+}
+
+/// What `find` should do next on the current level
+enum Step<K: Scan + GcDrop, V: Scan + ?Sized> {
+    Retry,
+    Unlinked(Option<Gc<SkipNode<K, V>>>),
+    Advance(Option<Gc<SkipNode<K, V>>>),
+    Stop,
+}
+
+type Preds<K, V> = Vec<Gc<SkipNode<K, V>>>;
+type Succs<K, V> = Vec<Option<Gc<SkipNode<K, V>>>>;
+
+impl<K: Scan + GcDrop + Ord, V: Scan + ?Sized> SkipListMap<K, V> {
+    /// Create a new, empty `SkipListMap`
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            head: Gc::new_with_finalizer(SkipNode {
+                entry: None,
+                next: (0..MAX_HEIGHT).map(|_| AtomicOptionGc::none()).collect(),
+            }),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Find the last node before `key` and the first node at or after `key`, on every level
+    ///
+    /// Any removed nodes we come across along the way are unlinked.
+    fn find<Q>(&self, key: &Q) -> (Preds<K, V>, Succs<K, V>)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        'retry: loop {
+            let mut preds = Vec::with_capacity(MAX_HEIGHT);
+            let mut succs = Vec::with_capacity(MAX_HEIGHT);
+
+            let mut pred = self.head.clone();
+            for level in (0..MAX_HEIGHT).rev() {
+                let mut curr = pred.get().next[level].load(Ordering::Acquire);
+                while let Some(node) = curr.take() {
+                    let step = {
+                        let node_guard = node.get();
+                        if node_guard.is_marker() {
+                            // `pred` was removed underneath us, so we need to start over
+                            Step::Retry
+                        } else {
+                            let next = node_guard.next[level].load(Ordering::Acquire);
+                            if let Some(marker) = next.as_ref().filter(|n| n.get().is_marker()) {
+                                // `node` was removed, so help unlink it
+                                let after = marker.get().next[0].load(Ordering::Acquire);
+                                if pred.get().next[level].compare_exchange(
+                                    Some(&node),
+                                    after.as_ref(),
+                                    Ordering::AcqRel,
+                                    Ordering::Acquire,
+                                ) {
+                                    Step::Unlinked(after)
+                                } else {
+                                    Step::Retry
+                                }
+                            } else if node_guard.key().borrow().cmp(key) == CmpOrdering::Less {
+                                Step::Advance(next)
+                            } else {
+                                Step::Stop
+                            }
+                        }
+                    };
+
+                    match step {
+                        Step::Retry => continue 'retry,
+                        Step::Unlinked(after) => curr = after,
+                        Step::Advance(next) => {
+                            pred = node;
+                            curr = next;
+                        }
+                        Step::Stop => {
+                            curr = Some(node);
+                            break;
+                        }
+                    }
+                }
+
+                preds.push(pred.clone());
+                succs.push(curr);
+            }
+
+            // We went from the top level down
+            preds.reverse();
+            succs.reverse();
+            return (preds, succs);
+        }
+    }
+
+    /// Find the node for `key`, if it's in the map
+    fn find_node<Q>(&self, key: &Q) -> Option<Gc<SkipNode<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (_, mut succs) = self.find(key);
+        let node = succs.swap_remove(0)?;
+
+        let found = node.get().key().borrow() == key;
+        if found {
+            Some(node)
+        } else {
+            None
+        }
+    }
+
+    /// Insert `value` at `key`, if there isn't already an entry for `key`
+    ///
+    /// Returns `true` if the entry was inserted, `false` if `key` was already in the map.
+    #[allow(clippy::must_use_candidate)]
+    pub fn insert(&self, key: K, value: Gc<V>) -> bool {
+        let height = random_height();
+        let node = Gc::new_with_finalizer(SkipNode {
+            entry: Some((key, value)),
+            next: (0..height).map(|_| AtomicOptionGc::none()).collect(),
+        });
+        let node_guard = node.get();
+        let key = node_guard.key();
+
+        // Linking the node in on the bottom level is what actually inserts it
+        let (mut preds, mut succs) = loop {
+            let (preds, succs) = self.find(key);
+            if let Some(succ) = &succs[0] {
+                if succ.get().key() == key {
+                    return false;
+                }
+            }
+
+            // The node isn't visible yet, so no-one else can be touching its `next`
+            node_guard.next[0].store(succs[0].as_ref(), Ordering::Release);
+            if preds[0].get().next[0].compare_exchange(
+                succs[0].as_ref(),
+                Some(&node),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                break (preds, succs);
+            }
+        };
+        self.len.fetch_add(1, Ordering::Relaxed);
+
+        // The upper levels just speed up searches
+        for level in 1..height {
+            loop {
+                // Once the node is visible it may be removed at any time, in which case we stop
+                let next = node_guard.next[level].load(Ordering::Acquire);
+                if is_marker(next.as_ref())
+                    || !node_guard.next[level].compare_exchange(
+                        next.as_ref(),
+                        succs[level].as_ref(),
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    )
+                {
+                    return true;
+                }
+
+                if preds[level].get().next[level].compare_exchange(
+                    succs[level].as_ref(),
+                    Some(&node),
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    break;
+                }
+
+                let (new_preds, new_succs) = self.find(key);
+                preds = new_preds;
+                succs = new_succs;
+            }
+        }
+
+        true
+    }
+
+    /// Remove the entry for `key`, returning its value if there was one
+    #[allow(clippy::must_use_candidate)]
+    pub fn remove<Q>(&self, key: &Q) -> Option<Gc<V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node = self.find_node(key)?;
+        let node_guard = node.get();
+
+        // Mark from the top down, so a node is never marked on some level it isn't linked on yet
+        for next in node_guard.next[1..].iter().rev() {
+            mark(next);
+        }
+        // Whoever marks the bottom level is the one who removed the entry
+        if !mark(&node_guard.next[0]) {
+            return None;
+        }
+        self.len.fetch_sub(1, Ordering::Relaxed);
+        let value = node_guard.value().clone();
+        drop(node_guard);
+
+        // Make sure the node is fully unlinked
+        self.find(key);
+
+        Some(value)
+    }
+
+    /// Get the value for `key`, if there is one
+    #[must_use]
+    pub fn get<Q>(&self, key: &Q) -> Option<Gc<V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node = self.find_node(key)?;
+        let value = node.get().value().clone();
+        Some(value)
+    }
+
+    /// Is there an entry for `key`?
+    #[must_use]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_node(key).is_some()
+    }
+
+    /// How many entries are in the map
+    ///
+    /// If the map is being modified concurrently, this is only an estimate.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// Is the map empty?
+    ///
+    /// Removed entries that haven't been unlinked yet are skipped over. But if the map is being
+    /// modified concurrently, the answer may already be out of date by the time you get it (or, for
+    /// entries inserted and removed while this looks, may not match any single moment).
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        let mut next = self.head.get().next[0].load(Ordering::Acquire);
+        while let Some(node) = next {
+            let succ = node.get().next[0].load(Ordering::Acquire);
+            match succ.as_ref().filter(|n| n.get().is_marker()) {
+                // This node has been removed, so look past it
+                Some(marker) => next = marker.get().next[0].load(Ordering::Acquire),
+                None => return false,
+            }
+        }
+
+        true
+    }
+
+    /// Iterate over the entries in the map, in order
+    ///
+    /// Keys are cloned, since the map may be changing underneath us. The iterator sees entries that
+    /// are in the map throughout the iteration, and may or may not see concurrent changes.
+    #[must_use]
+    pub fn iter(&self) -> SkipListMapIter<K, V>
+    where
+        K: Clone,
+    {
+        SkipListMapIter {
+            next: self.head.get().next[0].load(Ordering::Acquire),
+        }
+    }
+}
+
+impl<K: Scan + GcDrop + Ord, V: Scan + ?Sized> Default for SkipListMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Debug for SkipListMap<K, V>
+where
+    K: Scan + GcDrop + Ord + Clone + Debug,
+    V: Scan + Debug + ?Sized,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> IntoIterator for &SkipListMap<K, V>
+where
+    K: Scan + GcDrop + Ord + Clone,
+    V: Scan + ?Sized,
+{
+    type Item = (K, Gc<V>);
+    type IntoIter = SkipListMapIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+unsafe impl<K: Scan + GcDrop, V: Scan + ?Sized> Scan for SkipListMap<K, V> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        scanner.scan(&self.head);
+    }
+}
+
+unsafe impl<K: Scan + GcDrop, V: Scan + ?Sized> GcSafe for SkipListMap<K, V> {}
+// The head is a `Gc`, so the map itself doesn't actually contain any atomics
+unsafe impl<K: Scan + GcDrop, V: Scan + ?Sized> GcDrop for SkipListMap<K, V> {}
+unsafe impl<K, V> GcDeref for SkipListMap<K, V>
+where
+    K: Scan + GcDrop + Send + Sync,
+    V: Scan + Send + Sync + ?Sized,
+{
+}
+
+unsafe impl<K: Scan + GcDrop, V: Scan + ?Sized> Finalize for SkipListMap<K, V> {
+    unsafe fn finalize(&mut self) {
+        drop_in_place(self)
+    }
+}
+
+unsafe impl<K: Scan + GcDrop, V: Scan + ?Sized> Scan for SkipNode<K, V> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        scanner.scan(&self.entry);
+        scanner.scan(&self.next);
+    }
+}
+
+unsafe impl<K: Scan + GcDrop, V: Scan + ?Sized> GcSafe for SkipNode<K, V> {}
+
+// Keys are `GcDrop`, so we can just drop everything
+unsafe impl<K: Scan + GcDrop, V: Scan + ?Sized> Finalize for SkipNode<K, V> {
+    unsafe fn finalize(&mut self) {
+        drop_in_place(self)
+    }
+}
+
+/// An iterator over the entries of a `SkipListMap`, see `SkipListMap::iter`
+pub struct SkipListMapIter<K: Scan + GcDrop, V: Scan + ?Sized> {
+    next: Option<Gc<SkipNode<K, V>>>,
+}
+
+impl<K: Scan + GcDrop + Clone, V: Scan + ?Sized> Iterator for SkipListMapIter<K, V> {
+    type Item = (K, Gc<V>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = self.next.take()?;
+            let node_guard = node.get();
+
+            let next = node_guard.next[0].load(Ordering::Acquire);
+            if let Some(marker) = next.as_ref().filter(|n| n.get().is_marker()) {
+                // This node has been removed, so skip it
+                self.next = marker.get().next[0].load(Ordering::Acquire);
+            } else {
+                self.next = next;
+                return Some((node_guard.key().clone(), node_guard.value().clone()));
+            }
+        }
+    }
+}
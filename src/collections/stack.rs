@@ -0,0 +1,147 @@
+use std::fmt::{self, Debug, Formatter};
+use std::ptr::drop_in_place;
+use std::sync::atomic::Ordering;
+
+use crate::atomic::AtomicOptionGc;
+use crate::marker::{GcDeref, GcSafe};
+use crate::{Finalize, Gc, Scan, Scanner};
+
+/// A compare-and-swap based stack of `Gc<T>`s (a Treiber stack)
+///
+/// # Example
+/// ```
+/// use shredder::collections::Stack;
+/// use shredder::Gc;
+///
+/// let stack = Stack::new();
+/// stack.push(&Gc::new(1));
+/// stack.push(&Gc::new(2));
+///
+/// assert_eq!(*stack.pop().unwrap().get(), 2);
+/// assert_eq!(*stack.pop().unwrap().get(), 1);
+/// assert!(stack.pop().is_none());
+/// ```
+pub struct Stack<T: Scan + ?Sized> {
+    head: AtomicOptionGc<StackNode<T>>,
+}
+
+// Nodes never change once pushed, so only `push` touches `next` (before the node is published)
+struct StackNode<T: Scan + ?Sized> {
+    value: Gc<T>,
+    next: AtomicOptionGc<Self>,
+}
+
+impl<T: Scan + ?Sized> Stack<T> {
+    /// Create a new, empty `Stack`
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            head: AtomicOptionGc::none(),
+        }
+    }
+
+    /// Push `value` onto the top of the stack
+    pub fn push(&self, value: &Gc<T>) {
+        let mut head = self.head.load(Ordering::Acquire);
+        let node = Gc::new_with_finalizer(StackNode {
+            value: value.clone(),
+            next: AtomicOptionGc::new(head.as_ref()),
+        });
+
+        loop {
+            if self.head.compare_exchange(
+                head.as_ref(),
+                Some(&node),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                return;
+            }
+
+            // Nobody else can see `node` yet, so just point it at the new head and try again
+            head = self.head.load(Ordering::Acquire);
+            node.get().next.store(head.as_ref(), Ordering::Relaxed);
+        }
+    }
+
+    /// Pop the value on the top of the stack, if there is one
+    #[must_use]
+    pub fn pop(&self) -> Option<Gc<T>> {
+        loop {
+            let head = self.head.load(Ordering::Acquire)?;
+            let next = head.get().next.load(Ordering::Acquire);
+
+            // No ABA problem here: `head` can't be reused while we have a `Gc` to it
+            if self.head.compare_exchange(
+                Some(&head),
+                next.as_ref(),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                return Some(head.get().value.clone());
+            }
+        }
+    }
+
+    /// Get the value on the top of the stack (if there is one) without removing it
+    #[must_use]
+    pub fn peek(&self) -> Option<Gc<T>> {
+        let head = self.head.load(Ordering::Acquire)?;
+        let value = head.get().value.clone();
+        Some(value)
+    }
+
+    /// Is the stack empty right now?
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_none()
+    }
+}
+
+impl<T: Scan + ?Sized> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Scan + ?Sized> Debug for Stack<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stack")
+            .field("is_empty", &self.is_empty())
+            .finish()
+    }
+}
+
+unsafe impl<T: Scan + ?Sized> Scan for Stack<T> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        scanner.scan(&self.head);
+    }
+}
+
+unsafe impl<T: Scan + ?Sized> GcSafe for Stack<T> {}
+// unsafe impl<T: Scan + ?Sized> !GcDrop for Stack<T> {}
+unsafe impl<T: Scan + Send + Sync + ?Sized> GcDeref for Stack<T> {}
+
+unsafe impl<T: Scan + ?Sized> Finalize for Stack<T> {
+    unsafe fn finalize(&mut self) {
+        drop_in_place(self)
+    }
+}
+
+unsafe impl<T: Scan + ?Sized> Scan for StackNode<T> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        scanner.scan(&self.value);
+        scanner.scan(&self.next);
+    }
+}
+
+unsafe impl<T: Scan + ?Sized> GcSafe for StackNode<T> {}
+// unsafe impl<T: Scan + ?Sized> !GcDrop for StackNode<T> {}
+
+unsafe impl<T: Scan + ?Sized> Finalize for StackNode<T> {
+    unsafe fn finalize(&mut self) {
+        drop_in_place(self)
+    }
+}
@@ -1,7 +1,11 @@
-use std::alloc::{alloc, dealloc, Layout};
+use std::alloc::{alloc, dealloc, handle_alloc_error, GlobalAlloc, Layout};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
 use std::mem::{self, ManuallyDrop};
 use std::panic::UnwindSafe;
-use std::ptr;
+use std::ptr::{self, NonNull};
+
+use once_cell::sync::OnceCell;
 
 use crate::collector::InternalGcRef;
 use crate::marker::GcDrop;
@@ -14,6 +18,56 @@ pub struct GcAllocation {
     deallocation_action: DeallocationAction,
 }
 
+/// The error returned when there isn't enough memory to create a `Gc`, see `Gc::try_new`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AllocError;
+
+impl Display for AllocError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("could not allocate memory for a Gc")
+    }
+}
+
+impl Error for AllocError {}
+
+/// The error returned by `set_gc_allocator` if `Gc` data has already been allocated
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SetAllocatorError;
+
+impl Display for SetAllocatorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("the Gc allocator can't be changed once Gc data has been allocated")
+    }
+}
+
+impl Error for SetAllocatorError {}
+
+/// The allocator `Gc` data lives in. Fixed the first time anything is allocated
+static GC_ALLOCATOR: OnceCell<&'static (dyn GlobalAlloc + Sync)> = OnceCell::new();
+
+/// Forwards to the `#[global_allocator]`, which is what we use unless told otherwise
+struct ProcessAllocator;
+
+unsafe impl GlobalAlloc for ProcessAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        dealloc(ptr, layout)
+    }
+}
+
+pub fn set_allocator(
+    allocator: &'static (dyn GlobalAlloc + Sync),
+) -> Result<(), SetAllocatorError> {
+    GC_ALLOCATOR.set(allocator).map_err(|_| SetAllocatorError)
+}
+
+fn gc_allocator() -> &'static (dyn GlobalAlloc + Sync) {
+    *GC_ALLOCATOR.get_or_init(|| &ProcessAllocator)
+}
+
 /// What additional action should we run before deallocating?
 #[derive(Copy, Clone, Debug, Hash)]
 pub enum DeallocationAction {
@@ -43,6 +97,19 @@ impl GcAllocation {
         )
     }
 
+    /// Like `allocate_with_drop`, but for data that is only ever dropped on the thread that
+    /// allocated it (so it doesn't need to be `GcDrop`)
+    pub fn allocate_with_local_drop<T: Scan + 'static>(v: T) -> (Self, *const T) {
+        let (scan_ptr, raw_ptr) = Self::raw_allocate(v);
+        (
+            Self {
+                scan_ptr,
+                deallocation_action: DeallocationAction::RunDrop,
+            },
+            raw_ptr,
+        )
+    }
+
     pub fn allocate_no_drop<T: Scan>(v: T) -> (Self, *const T) {
         let (scan_ptr, raw_ptr) = Self::raw_allocate(v);
         (
@@ -74,12 +141,32 @@ impl GcAllocation {
 
     /// This allocates a piece of data, but leaves it uninitialized for your pleasure
     pub fn allocate_uninitialized_with_drop<T: Scan + GcDrop>() -> (Self, *const T) {
+        Self::try_allocate_uninitialized_with_drop()
+            .unwrap_or_else(|_| handle_alloc_error(Layout::new::<T>()))
+    }
+
+    /// Like `allocate_uninitialized_with_drop`, but reports running out of memory
+    pub fn try_allocate_uninitialized_with_drop<T: Scan + GcDrop>(
+    ) -> Result<(Self, *const T), AllocError> {
+        let (scan_ptr, data_ptr) = Self::try_raw_allocate_uninitialized::<T>()?;
+
+        Ok((
+            Self {
+                scan_ptr,
+                deallocation_action: DeallocationAction::RunDrop,
+            },
+            data_ptr,
+        ))
+    }
+
+    /// This allocates a piece of data, but leaves it uninitialized for your pleasure
+    pub fn allocate_uninitialized_no_drop<T: Scan>() -> (Self, *const T) {
         let (scan_ptr, data_ptr) = Self::raw_allocate_uninitialized::<T>();
 
         (
             Self {
                 scan_ptr,
-                deallocation_action: DeallocationAction::RunDrop,
+                deallocation_action: DeallocationAction::DoNothing,
             },
             data_ptr,
         )
@@ -121,29 +208,36 @@ impl GcAllocation {
     /// This allocates a nice old' piece of uninitialized memory. This is safe as long as you don't
     /// access this uninitialized memory, or track the data before you initialize it.
     fn raw_allocate_uninitialized<'a, T: Scan + 'a>() -> (*const dyn Scan, *const T) {
-        let data_ptr = unsafe { alloc(Layout::new::<T>()) as *const T };
+        Self::try_raw_allocate_uninitialized::<T>()
+            .unwrap_or_else(|_| handle_alloc_error(Layout::new::<T>()))
+    }
+
+    /// `raw_allocate_uninitialized`, but giving back an error if the allocator comes up empty
+    fn try_raw_allocate_uninitialized<'a, T: Scan + 'a>(
+    ) -> Result<(*const dyn Scan, *const T), AllocError> {
+        let layout = Layout::new::<T>();
+        // A `GlobalAlloc` can't be asked for zero bytes, so zero-sized data gets a dangling pointer
+        let data_ptr = if layout.size() == 0 {
+            NonNull::<T>::dangling().as_ptr().cast_const()
+        } else {
+            unsafe { gc_allocator().alloc(layout) as *const T }
+        };
+        if data_ptr.is_null() {
+            return Err(AllocError);
+        }
 
         let fat_ptr: *const (dyn Scan + 'a) = data_ptr;
         // The contract of `Scan` ensures the `scan` method can be called after lifetimes end
         let fat_ptr: *const dyn Scan = unsafe { mem::transmute(fat_ptr) };
 
-        (fat_ptr, data_ptr)
+        Ok((fat_ptr, data_ptr))
     }
 
     fn raw_allocate<'a, T: Scan + 'a>(v: T) -> (*const dyn Scan, *const T) {
+        let (fat_ptr, data_ptr) = Self::raw_allocate_uninitialized::<T>();
         // This is a straightforward use of alloc/write -- it should be undef free
-        let data_ptr = unsafe {
-            let heap_space = alloc(Layout::new::<T>()).cast();
-            ptr::write(heap_space, v);
-            // NOTE: Write moves the data into the heap
-
-            // Heap space is now a pointer to a T
-            heap_space as *const T
-        };
-
-        let fat_ptr: *const (dyn Scan + 'a) = data_ptr;
-        // The contract of `Scan` ensures the `scan` method can be called after lifetimes end
-        let fat_ptr: *const dyn Scan = unsafe { mem::transmute(fat_ptr) };
+        // NOTE: Write moves the data into the heap
+        unsafe { ptr::write(data_ptr.cast_mut(), v) };
 
         (fat_ptr, data_ptr)
     }
@@ -156,9 +250,8 @@ impl GcAllocation {
         match self.deallocation_action {
             DeallocationAction::DoNothing => {
                 // The name here is a bit of a lie, because we still need to invalidate handles
-                let mut scanner = Scanner::new(|h| {
-                    h.invalidate();
-                });
+                let mut invalidate = |h: InternalGcRef| h.invalidate();
+                let mut scanner = Scanner::new(&mut invalidate);
                 (&*scan_ptr).scan(&mut scanner);
             }
             DeallocationAction::RunDrop => {
@@ -172,9 +265,8 @@ impl GcAllocation {
                 // First of all invalidate handles, just in case of a bad `Finalize` implementation
                 // (If it doesn't delegate correctly, `Gc`s could be left dangling)
                 {
-                    let mut scanner = Scanner::new(|h| {
-                        h.invalidate();
-                    });
+                    let mut invalidate = |h: InternalGcRef| h.invalidate();
+                    let mut scanner = Scanner::new(&mut invalidate);
                     (&*scan_ptr).scan(&mut scanner);
                 }
 
@@ -195,17 +287,38 @@ impl GcAllocation {
         }
 
         // Only call dealloc() if we're not dealing with a boxed value, because the box gets
-        // dropped above.
+        // dropped above. Zero-sized data was never allocated, so there's nothing to give back.
         if !matches!(self.deallocation_action, DeallocationAction::BoxDrop) {
             let dealloc_layout = Layout::for_value(&*scan_ptr);
-            let heap_ptr = scan_ptr as *mut u8;
-            dealloc(heap_ptr, dealloc_layout);
+            if dealloc_layout.size() != 0 {
+                let heap_ptr = scan_ptr as *mut u8;
+                gc_allocator().dealloc(heap_ptr, dealloc_layout);
+            }
+        }
+    }
+
+    /// How many bytes the value in this allocation takes up. Only safe to call before it's been
+    /// deallocated
+    pub unsafe fn size(&self) -> usize {
+        mem::size_of_val(&*self.scan_ptr)
+    }
+
+    /// Give back the memory for this allocation, without running anything on the value in it.
+    /// This is only safe if this allocation was created for a `T` that has already been moved out
+    /// (or was never initialized), and the collector will never touch this allocation again.
+    pub unsafe fn release_memory<T>(self) {
+        if matches!(self.deallocation_action, DeallocationAction::BoxDrop) {
+            // Let the box work out how to give back its memory (it may not have allocated any)
+            drop(Box::from_raw(self.scan_ptr as *mut ManuallyDrop<T>));
+        } else if mem::size_of::<T>() != 0 {
+            let heap_ptr = self.scan_ptr as *mut u8;
+            gc_allocator().dealloc(heap_ptr, Layout::new::<T>());
         }
     }
 
-    pub fn scan<F: FnMut(InternalGcRef)>(&self, callback: F) {
+    pub fn scan<F: FnMut(InternalGcRef)>(&self, mut callback: F) {
         unsafe {
-            let mut scanner = Scanner::new(callback);
+            let mut scanner = Scanner::new(&mut callback);
             let to_scan = &*self.scan_ptr;
             to_scan.scan(&mut scanner);
         }
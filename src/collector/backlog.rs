@@ -0,0 +1,98 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::collector::GcData;
+
+/// How much garbage may be waiting for its destructors before allocating a `Gc` waits for it
+///
+/// See `set_gc_pending_destruction_limit`. The default has no limits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PendingDestructionLimit {
+    /// wait once more than this many pieces of garbage are waiting
+    pub objects: Option<usize>,
+    /// wait once the garbage waiting takes up more than this many bytes
+    pub bytes: Option<usize>,
+}
+
+impl PendingDestructionLimit {
+    fn exceeded_by(&self, objects: usize, bytes: usize) -> bool {
+        self.objects.is_some_and(|limit| objects > limit)
+            || self.bytes.is_some_and(|limit| bytes > limit)
+    }
+}
+
+/// Keeps count of the garbage sent to the drop thread that hasn't been destroyed yet
+#[derive(Debug, Default)]
+pub(super) struct Backlog {
+    objects: AtomicUsize,
+    bytes: AtomicUsize,
+    limit: Mutex<PendingDestructionLimit>,
+    /// is there any limit? (So allocations can skip the lock when there isn't)
+    limited: AtomicBool,
+    /// how many threads are waiting for the backlog to shrink
+    waiting: AtomicUsize,
+    shrunk: Condvar,
+}
+
+impl Backlog {
+    pub fn objects(&self) -> usize {
+        self.objects.load(Ordering::SeqCst)
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes.load(Ordering::SeqCst)
+    }
+
+    pub fn set_limit(&self, limit: PendingDestructionLimit) {
+        *self.limit.lock() = limit;
+        self.limited.store(
+            limit != PendingDestructionLimit::default(),
+            Ordering::SeqCst,
+        );
+        // The limit may have gone up, so everyone waiting should check again
+        self.shrunk.notify_all();
+    }
+
+    /// Note that `garbage` has been sent to the drop thread
+    pub fn add(&self, garbage: &[Arc<GcData>]) {
+        let bytes: usize = garbage
+            .iter()
+            .map(|data| unsafe { data.underlying_allocation.size() })
+            .sum();
+
+        self.objects.fetch_add(garbage.len(), Ordering::SeqCst);
+        self.bytes.fetch_add(bytes, Ordering::SeqCst);
+    }
+
+    /// Note that a piece of garbage `bytes` in size is no longer waiting to be destroyed
+    pub fn remove(&self, bytes: usize) {
+        self.objects.fetch_sub(1, Ordering::SeqCst);
+        self.bytes.fetch_sub(bytes, Ordering::SeqCst);
+
+        // Taking the lock means a thread that's about to wait can't miss this
+        if self.waiting.load(Ordering::SeqCst) > 0 {
+            let _limit = self.limit.lock();
+            self.shrunk.notify_all();
+        }
+    }
+
+    /// Block until the backlog is back within its limit
+    pub fn wait_for_room(&self) {
+        if !self.limited.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let mut limit = self.limit.lock();
+        if !limit.exceeded_by(self.objects(), self.bytes()) {
+            return;
+        }
+
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        while limit.exceeded_by(self.objects(), self.bytes()) {
+            self.shrunk.wait(&mut limit);
+        }
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+    }
+}
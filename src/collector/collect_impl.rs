@@ -1,13 +1,14 @@
-use std::sync::atomic::Ordering;
+use std::iter;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
 use crossbeam::deque::Injector;
 use crossbeam::queue::SegQueue;
-use dynqueue::IntoDynQueue;
-use parking_lot::{MutexGuard, RwLock};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use parking_lot::MutexGuard;
 
-use crate::collector::dropper::DropMessage;
-use crate::collector::{Collector, GcExclusiveWarrant};
+use crate::collector::marker::mark_in_parallel;
+use crate::collector::{Collector, GcExclusiveWarrant, GcHandle, GcParallelism, UnderlyingData};
 use crate::concurrency::lockout::Lockout;
 
 impl Collector {
@@ -18,9 +19,13 @@ impl Collector {
         // (there is nowhere a new "secret root" can be created and then the old root stashed and seen as non-rooted)
         // - New data is treated as a special case, and only deallocated if it existed at the start of collection
         // - Deleted handles cannot make the graph "more connected" if the deletion was not observed
+        // - Atomics can change at any time, but while we're marking anything read out of (or
+        // overwritten in) an atomic is shaded, so we'll mark it too
 
         trace!("Beginning collection");
-        let _atomic_spinlock_guard = self.atomic_spinlock.lock_exclusive();
+
+        // Stick to one way of spreading out work for the whole collection
+        let parallelism = self.parallelism();
 
         let current_collection = self
             .tracked_data
@@ -33,6 +38,16 @@ impl Collector {
         // but may slow direct calls to `collect`.
         self.synchronize_destructors();
 
+        // The pacer wants to know how long the collection itself takes
+        let started = Instant::now();
+
+        // Turn on the atomic deletion barrier. Taking the spinlock means no atomic operation is
+        // halfway done, so every one either happened before marking or will shade what it touches
+        {
+            let _atomic_spinlock_guard = self.atomic_spinlock.lock_exclusive();
+            self.marking.store(true, Ordering::SeqCst);
+        }
+
         // The warrant system prevents us from scanning in-use data
         let warrants: Injector<GcExclusiveWarrant> = Injector::new();
 
@@ -40,7 +55,7 @@ impl Collector {
         // eprintln!("tracked handles {:?}", tracked_handles);
 
         // In this step we calculate what's not rooted by marking all data definitively in a Gc
-        self.tracked_data.data.par_iter(|data| {
+        parallelism.for_each_tracked(&self.tracked_data.data, |data| {
             // If data.last_marked == 0, then it is new data. Update that we've seen this data
             // (this step helps synchronize what data is valid to be deallocated)
             if data.last_marked.load(Ordering::SeqCst) == 0 {
@@ -68,7 +83,7 @@ impl Collector {
 
         // The handles that were not just marked need to be treated as roots
         let roots = SegQueue::new();
-        self.tracked_data.handles.par_iter(|handle| {
+        parallelism.for_each_tracked(&self.tracked_data.handles, |handle| {
             // If the `last_non_rooted` number was not now, then it is a root
             if handle.last_non_rooted.load(Ordering::SeqCst) != current_collection {
                 roots.push(handle);
@@ -79,87 +94,109 @@ impl Collector {
 
         // This step is dfs through the object graph (starting with the roots)
         // We mark each object we find
-        let dfs_stack = roots.into_dyn_queue();
-        dfs_stack
-            .into_par_iter()
-            .for_each(|(queue, handle)| unsafe {
-                handle.underlying_data.with_data(|data| {
-                    // If this data is new, we don't want to `Scan` it, since we may not have its Lockout
-                    // Any handles inside this could not of been seen in step 1, so they'll be rooted anyway
-                    if data.last_marked.load(Ordering::SeqCst) != 0 {
-                        // Essential note! All non-new non-warranted data is automatically marked
-                        // Thus we will never accidentally scan non-warranted data here
-                        let previous_mark =
-                            data.last_marked.swap(current_collection, Ordering::SeqCst);
-
-                        // Since we've done an atomic swap, we know we've already scanned this iff it was marked
-                        // (excluding data marked because we couldn't get its warrant, who's handles would be seen as roots)
-                        // This stops us for scanning data more than once and, crucially, concurrently scanning the same data
-                        if previous_mark != current_collection {
-                            data.last_marked.store(current_collection, Ordering::SeqCst);
-
-                            data.underlying_allocation.scan(|h| {
-                                let mut should_enque = false;
-                                h.handle_ref.v.underlying_data.with_data(|scanned_data| {
-                                    if scanned_data.last_marked.load(Ordering::SeqCst)
-                                        != current_collection
-                                    {
-                                        should_enque = true;
-                                    }
-                                });
-                                if should_enque {
-                                    queue.enqueue(h.handle_ref.v);
-                                }
-                            });
-                        }
-                    }
-                })
-            });
-        // We're done scanning things, and have established what is marked. Release the warrants
-        drop(warrants);
-
-        // Now cleanup by removing all the data that is done for
-        let to_drop = RwLock::new(Vec::new());
-
-        self.tracked_data.data.par_retain(|data| {
-            // Mark the new data as in use for now
-            // This stops us deallocating data that was allocated during collection
-            if data.last_marked.load(Ordering::SeqCst) == 0 {
-                data.last_marked.store(current_collection, Ordering::SeqCst);
-            }
-
-            // If this is true, we just marked this data
-            if data.last_marked.load(Ordering::SeqCst) == current_collection {
-                // so retain it
-                true
-            } else {
-                // Otherwise we didn't mark it and it should be deallocated
-                // eprintln!("deallocating {:?}", data_ptr);
-                // Send it to the drop thread to be dropped
-                to_drop.write().push(data.clone());
-
-                // Don't retain this data
-                false
+        Self::mark_from(&roots, current_collection, &parallelism);
+
+        // Now mark whatever the atomics shaded while we were marking, until there's nothing left
+        loop {
+            let shaded = SegQueue::new();
+            {
+                let _atomic_spinlock_guard = self.atomic_spinlock.lock_exclusive();
+                if self.shaded.is_empty() {
+                    // No atomic operation is running, so nothing more can be shaded after this
+                    self.marking.store(false, Ordering::SeqCst);
+                    break;
+                }
+
+                while let Some(data) = self.shaded.pop() {
+                    // The dfs works on handles, so give each piece of data a temporary one
+                    shaded.push(Arc::new(GcHandle {
+                        underlying_data: UnderlyingData::Fixed(data),
+                        last_non_rooted: AtomicU64::new(0),
+                    }));
+                }
             }
-        });
 
-        // Send off the data to be dropped in the background
-        let drop_msg = DropMessage::DataToDrop(to_drop);
-        if let Err(e) = self.dropper.send_msg(drop_msg) {
-            error!("Error sending to drop thread {}", e);
+            Self::mark_from(&shaded, current_collection, &parallelism);
         }
 
-        // update the trigger based on the new baseline
-        self.trigger
-            .set_data_count_after_collection(self.tracked_data_count());
+        // We're done scanning things, and have established what is marked. Release the warrants
+        drop(warrants);
+
+        // Everything we didn't mark is garbage, but we sweep it up later, outside the `gc_lock`
+        self.start_sweep(current_collection, parallelism);
 
         // update collection number
         self.tracked_data
             .current_collection_number
             .fetch_add(1, Ordering::SeqCst);
 
+        self.trigger.record_collection(started.elapsed());
+
         drop(gc_guard);
 
+        // Reference counting may have been held up by this collection, so free anything it found
+        self.free_unreferenced();
+
+        // The async gc thread sweeps in the background
+        self.notify_async_gc_thread();
+
         trace!("Collection finished");
     }
+
+    /// Mark everything reachable from `roots`, scanning data we haven't marked yet
+    ///
+    /// Only safe to call while we're holding the warrants from the start of collection
+    fn mark_from(
+        roots: &SegQueue<Arc<GcHandle>>,
+        current_collection: u64,
+        parallelism: &GcParallelism,
+    ) {
+        if parallelism.is_sequential() {
+            let mut dfs_stack: Vec<_> = iter::from_fn(|| roots.pop()).collect();
+            while let Some(handle) = dfs_stack.pop() {
+                unsafe { Self::mark_handle(&handle, current_collection, |h| dfs_stack.push(h)) };
+            }
+        } else {
+            unsafe { mark_in_parallel(roots, current_collection, parallelism) };
+        }
+    }
+
+    /// Mark the data behind `handle`, and `enqueue` the handles inside it (if we hadn't already)
+    ///
+    /// Only safe to call while we're holding the warrants from the start of collection
+    pub(super) unsafe fn mark_handle<F: FnMut(Arc<GcHandle>)>(
+        handle: &GcHandle,
+        current_collection: u64,
+        mut enqueue: F,
+    ) {
+        handle.underlying_data.with_data(|data| {
+            // If this data is new, we don't want to `Scan` it, since we may not have its Lockout
+            // Any handles inside this could not of been seen in step 1, so they'll be rooted anyway
+            if data.last_marked.load(Ordering::SeqCst) != 0 {
+                // Essential note! All non-new non-warranted data is automatically marked
+                // Thus we will never accidentally scan non-warranted data here
+                let previous_mark = data.last_marked.swap(current_collection, Ordering::SeqCst);
+
+                // Since we've done an atomic swap, we know we've already scanned this iff it was marked
+                // (excluding data marked because we couldn't get its warrant, who's handles would be seen as roots)
+                // This stops us for scanning data more than once and, crucially, concurrently scanning the same data
+                if previous_mark != current_collection {
+                    data.last_marked.store(current_collection, Ordering::SeqCst);
+
+                    data.underlying_allocation.scan(|h| {
+                        let mut should_enque = false;
+                        h.handle_ref.v.underlying_data.with_data(|scanned_data| {
+                            if scanned_data.last_marked.load(Ordering::SeqCst) != current_collection
+                            {
+                                should_enque = true;
+                            }
+                        });
+                        if should_enque {
+                            enqueue(h.handle_ref.v);
+                        }
+                    });
+                }
+            }
+        });
+    }
 }
@@ -1,7 +1,11 @@
-use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+
+use once_cell::sync::OnceCell;
 
 use crate::collector::alloc::GcAllocation;
+use crate::collector::local::LocalGarbage;
+use crate::concurrency::chunked_ll::CLLSlot;
 use crate::concurrency::lockout::{Lockout, LockoutProvider};
 use crate::Scan;
 
@@ -12,9 +16,21 @@ pub struct GcData {
     pub(crate) lockout: Lockout,
     /// have we started deallocating this piece of data yet?
     pub(crate) deallocated: AtomicBool,
+    /// is this garbage waiting its turn to be destroyed in order? (If so the destructors of data
+    /// pointing to it may still look at it, see `set_ordered_destruction`)
+    pub(crate) awaiting_destruction: AtomicBool,
     // During what collection was this last marked?
     //     0 if this is a new piece of data
     pub(crate) last_marked: AtomicU64,
+    /// how many (non-atomic) handles point to this data
+    pub(crate) handle_count: AtomicUsize,
+    /// has this data ever been stored in an atomic? (Atomics aren't counted in `handle_count`, so
+    /// then only a collection can tell if this data is garbage)
+    pub(crate) in_atomic: AtomicBool,
+    /// where this data is in the collector's list of tracked data (once it's being tracked)
+    pub(crate) tracked_slot: OnceCell<CLLSlot<Self>>,
+    /// the thread this data must be destroyed on, if it can't be destroyed in the background
+    pub(crate) home: Option<Weak<LocalGarbage>>,
     /// a wrapper to manage (ie deallocate) the underlying allocation
     pub(crate) underlying_allocation: GcAllocation,
 }
@@ -29,6 +45,11 @@ impl GcData {
     pub fn scan_ptr(&self) -> *const dyn Scan {
         self.underlying_allocation.scan_ptr
     }
+
+    /// Note that this data is about to be stored in an atomic
+    pub(crate) fn mark_in_atomic(&self) {
+        self.in_atomic.store(true, Ordering::SeqCst);
+    }
 }
 
 /// There is one `GcHandle` per `Gc<T>`. We need this metadata for collection
@@ -48,15 +69,20 @@ pub enum UnderlyingData {
 }
 
 impl UnderlyingData {
-    // Safe only if called when the data is known to be live, and you know atomics can't be modified
-    // (Basically only okay to call in the collector itself)
+    // Safe only if called when the data is known to be live (basically only okay to call in the
+    // collector itself). Atomics may be modified concurrently, but the atomic deletion barrier
+    // makes sure whatever they pointed to stays alive until the collection is over
+    //
+    // Atomics may be null, in which case there is no data, and `f` is not called
     #[inline]
     pub unsafe fn with_data<F: FnOnce(&GcData)>(&self, f: F) {
         match self {
             Self::Fixed(data) => f(&*data),
             Self::DynamicForAtomic(ptr) => {
                 let arc_ptr = ptr.load(Ordering::Relaxed);
-                f(&*arc_ptr)
+                if !arc_ptr.is_null() {
+                    f(&*arc_ptr)
+                }
             }
         }
     }
@@ -1,22 +1,28 @@
 use std::panic::catch_unwind;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::thread::spawn;
+use std::thread::{spawn, yield_now};
 
 use crossbeam::channel::{self, SendError, Sender};
-use parking_lot::RwLock;
-use rayon::iter::IntoParallelRefIterator;
-use rayon::iter::ParallelIterator;
 
-use crate::collector::GcData;
+use crate::collector::backlog::Backlog;
+use crate::collector::order::destruction_order;
+use crate::collector::refcount::while_freeing;
+use crate::collector::{GcData, GcParallelism};
+use crate::concurrency::lockout::Lockout;
 
 pub(crate) struct BackgroundDropper {
     sender: Sender<DropMessage>,
+    /// the garbage that's been sent but not destroyed yet
+    backlog: Arc<Backlog>,
 }
 
 pub(crate) enum DropMessage {
     /// Signals the `BackgroundDropper` to deallocate the following data (possibly running some destructor)
-    DataToDrop(RwLock<Vec<Arc<GcData>>>),
+    /// Work is spread out like the collection that found the data
+    DataToDrop(Vec<Arc<GcData>>, GcParallelism),
+    /// Like `DataToDrop`, but the data is destroyed one at a time, referrers first (see `order.rs`)
+    DataToDropInOrder(Vec<Arc<GcData>>),
     /// Indicates to the `BackgroundDropper` that it should sync up with the calling code
     SyncUp(Sender<()>),
 }
@@ -24,32 +30,27 @@ pub(crate) enum DropMessage {
 impl BackgroundDropper {
     pub fn new() -> Self {
         let (sender, receiver) = channel::unbounded();
+        let backlog = Arc::new(Backlog::default());
 
         // The drop thread deals with doing all the Drops this collector needs to do
+        let drop_thread_backlog = backlog.clone();
         spawn(move || {
+            let backlog = &*drop_thread_backlog;
+
             // An Err value means the stream will never recover
             while let Ok(drop_msg) = receiver.recv() {
                 match drop_msg {
-                    DropMessage::DataToDrop(to_drop) => {
-                        let to_drop = to_drop.read();
-
+                    DropMessage::DataToDrop(to_drop, parallelism) => {
                         // NOTE: It's important that all data is correctly marked as deallocated before we start
-                        to_drop.par_iter().for_each(|data| {
+                        parallelism.for_each(&to_drop, |data| {
                             // Mark this data as in the process of being deallocated and unsafe to access
                             data.deallocated.store(true, Ordering::SeqCst);
                         });
 
                         // Then run the drops if needed
-                        to_drop.par_iter().for_each(|data| {
-                            let underlying_allocation = data.underlying_allocation;
-                            let res = catch_unwind(move || unsafe {
-                                underlying_allocation.deallocate();
-                            });
-                            if let Err(e) = res {
-                                eprintln!("Gc background drop failed: {:?}", e);
-                            }
-                        });
+                        parallelism.for_each(&to_drop, |data| destroy(data, backlog));
                     }
+                    DropMessage::DataToDropInOrder(to_drop) => drop_in_order(to_drop, backlog),
                     DropMessage::SyncUp(responder) => {
                         if let Err(e) = responder.send(()) {
                             eprintln!("Gc background syncup failed: {:?}", e);
@@ -59,10 +60,62 @@ impl BackgroundDropper {
             }
         });
 
-        Self { sender }
+        Self { sender, backlog }
     }
 
     pub fn send_msg(&self, msg: DropMessage) -> Result<(), SendError<DropMessage>> {
+        match &msg {
+            DropMessage::DataToDrop(to_drop, _) | DropMessage::DataToDropInOrder(to_drop) => {
+                self.backlog.add(to_drop);
+            }
+            DropMessage::SyncUp(_) => {}
+        }
+
         self.sender.send(msg)
     }
+
+    pub fn backlog(&self) -> &Backlog {
+        &self.backlog
+    }
+}
+
+/// Destroy garbage one piece at a time, referrers first (see `order.rs`)
+fn drop_in_order(to_drop: Vec<Arc<GcData>>, backlog: &Backlog) {
+    // Until its turn, garbage can be looked at by the destructors of data pointing to it
+    for data in &to_drop {
+        data.awaiting_destruction.store(true, Ordering::SeqCst);
+    }
+
+    for data in unsafe { destruction_order(to_drop) } {
+        data.awaiting_destruction.store(false, Ordering::SeqCst);
+
+        // Wait for anyone still looking at the data. Anyone new will see it's deallocated
+        let warrant = loop {
+            if let Some(warrant) = Lockout::get_exclusive_warrant(data.clone()) {
+                break warrant;
+            }
+            yield_now();
+        };
+        drop(warrant);
+
+        destroy(&data, backlog);
+    }
+}
+
+/// Run the destructor of a piece of garbage (or send it back to its own thread), taking it off the
+/// backlog
+fn destroy(data: &Arc<GcData>, backlog: &Backlog) {
+    let underlying_allocation = data.underlying_allocation;
+    let size = unsafe { underlying_allocation.size() };
+
+    if !data.send_home() {
+        let res = catch_unwind(move || unsafe {
+            while_freeing(|| underlying_allocation.deallocate());
+        });
+        if let Err(e) = res {
+            eprintln!("Gc background drop failed: {e:?}");
+        }
+    }
+
+    backlog.remove(size);
 }
@@ -0,0 +1,76 @@
+use std::cell::Cell;
+use std::panic::catch_unwind;
+use std::sync::Arc;
+
+use crossbeam::queue::SegQueue;
+
+use crate::collector::alloc::GcAllocation;
+use crate::collector::refcount::is_freeing;
+use crate::collector::{Collector, GcData, InternalGcRef};
+use crate::Scan;
+
+/// Garbage that has to be destroyed on the thread that allocated it
+pub(crate) type LocalGarbage = SegQueue<Arc<GcData>>;
+
+thread_local! {
+    /// The garbage this thread has to destroy (see `Collector::run_local_destructors`)
+    static LOCAL_GARBAGE: Arc<LocalGarbage> = Arc::new(LocalGarbage::new());
+    /// Is this thread destroying its local garbage right now?
+    static RUNNING_LOCAL: Cell<bool> = const { Cell::new(false) };
+}
+
+impl GcData {
+    /// If this data must be destroyed by the thread that allocated it, send it back there. Returns
+    /// false if it can be deallocated here and now
+    ///
+    /// If that thread has exited, there's nowhere left to run the destructor, so the data is leaked.
+    pub(crate) fn send_home(self: &Arc<Self>) -> bool {
+        match &self.home {
+            Some(home) => {
+                if let Some(home) = home.upgrade() {
+                    home.push(self.clone());
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Collector {
+    pub fn track_with_local_drop<T: Scan + 'static>(&self, data: T) -> (InternalGcRef, *const T) {
+        let (gc_data_ptr, heap_ptr) = GcAllocation::allocate_with_local_drop(data);
+        let home = LOCAL_GARBAGE.with(Arc::downgrade);
+
+        let (tracking_token, reference) = self.setup_gc_reference(gc_data_ptr, Some(home));
+        self.track_from_token(tracking_token);
+        (reference, heap_ptr)
+    }
+
+    /// Run the destructors of any garbage that was sent back to this thread
+    ///
+    /// This does nothing if we're already running destructors (including this thread's local
+    /// ones), since we may be holding up a collection, and anything new will be picked up by the
+    /// loop that's already running.
+    #[allow(clippy::unused_self)]
+    pub fn run_local_destructors(&self) {
+        if is_freeing() || RUNNING_LOCAL.with(|running| running.replace(true)) {
+            return;
+        }
+
+        // If the thread is exiting, the garbage is already gone (see `send_home`)
+        let _ = LOCAL_GARBAGE.try_with(|garbage| {
+            while let Some(data) = garbage.pop() {
+                let underlying_allocation = data.underlying_allocation;
+                let res = catch_unwind(move || unsafe {
+                    underlying_allocation.deallocate();
+                });
+                if let Err(e) = res {
+                    eprintln!("Gc local drop failed: {e:?}");
+                }
+            }
+        });
+
+        RUNNING_LOCAL.with(|running| running.set(false));
+    }
+}
@@ -0,0 +1,110 @@
+use std::iter;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crossbeam::deque::{Injector, Steal, Stealer, Worker};
+use crossbeam::queue::SegQueue;
+use crossbeam::utils::Backoff;
+
+use crate::collector::{Collector, GcHandle, GcParallelism};
+
+/// Mark everything reachable from `roots`, with a marker on each thread of the pool
+///
+/// Each marker keeps its own mark stack, so following an edge is just a push and pop on a local
+/// deque. When a marker runs dry it steals half of someone else's stack in one go. Deep structures
+/// (where there's little parallelism to be had) then cost about as much as marking sequentially,
+/// and wide ones get spread out in big batches rather than handle by handle.
+///
+/// Only safe to call while we're holding the warrants from the start of collection
+pub(super) unsafe fn mark_in_parallel(
+    roots: &SegQueue<Arc<GcHandle>>,
+    current_collection: u64,
+    parallelism: &GcParallelism,
+) {
+    let injector = Injector::new();
+    for root in iter::from_fn(|| roots.pop()) {
+        injector.push(root);
+    }
+
+    parallelism.install(|| {
+        let threads = rayon::current_num_threads();
+        let workers: Vec<_> = (0..threads).map(|_| Worker::new_lifo()).collect();
+        let stealers: Vec<_> = workers.iter().map(Worker::stealer).collect();
+        let termination = Termination::default();
+
+        rayon::scope(|scope| {
+            for worker in workers {
+                let (injector, stealers, termination) = (&injector, &stealers, &termination);
+                scope.spawn(move |_| unsafe {
+                    run_marker(&worker, injector, stealers, termination, current_collection);
+                });
+            }
+        });
+    });
+}
+
+/// Keeps track of how many markers are out of work
+///
+/// Markers only count once they've started. A pool thread that's busy with something else may never
+/// get around to starting its marker, and we don't want to wait on it (its stack is empty anyway).
+#[derive(Default)]
+struct Termination {
+    started: AtomicUsize,
+    idle: AtomicUsize,
+}
+
+impl Termination {
+    /// Have all the started markers run out of work? If so, there's no work left anywhere
+    fn is_done(&self) -> bool {
+        self.idle.load(Ordering::SeqCst) == self.started.load(Ordering::SeqCst)
+    }
+}
+
+unsafe fn run_marker(
+    worker: &Worker<Arc<GcHandle>>,
+    injector: &Injector<Arc<GcHandle>>,
+    stealers: &[Stealer<Arc<GcHandle>>],
+    termination: &Termination,
+    current_collection: u64,
+) {
+    termination.started.fetch_add(1, Ordering::SeqCst);
+
+    let backoff = Backoff::new();
+    let mut idle = false;
+    loop {
+        if let Some(handle) = find_work(worker, injector, stealers) {
+            if idle {
+                termination.idle.fetch_sub(1, Ordering::SeqCst);
+                idle = false;
+            }
+            backoff.reset();
+
+            Collector::mark_handle(&handle, current_collection, |h| worker.push(h));
+        } else {
+            if !idle {
+                termination.idle.fetch_add(1, Ordering::SeqCst);
+                idle = true;
+            }
+            if termination.is_done() {
+                return;
+            }
+            backoff.snooze();
+        }
+    }
+}
+
+/// Pop from our own stack, or else steal a batch from the roots or another marker
+fn find_work<T>(local: &Worker<T>, injector: &Injector<T>, stealers: &[Stealer<T>]) -> Option<T> {
+    local.pop().or_else(|| {
+        iter::repeat_with(|| {
+            injector.steal_batch_and_pop(local).or_else(|| {
+                stealers
+                    .iter()
+                    .map(|s| s.steal_batch_and_pop(local))
+                    .collect()
+            })
+        })
+        .find(|steal| !steal.is_retry())
+        .and_then(Steal::success)
+    })
+}
@@ -1,20 +1,35 @@
 mod alloc;
+mod backlog;
 mod collect_impl;
 mod data;
 mod dropper;
+mod local;
+mod marker;
+mod order;
+mod parallelism;
+mod pause;
+mod refcount;
+mod schedule;
+mod sweep;
 mod trigger;
 
-use std::ptr;
-use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering};
-use std::sync::Arc;
-use std::thread::spawn;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::{spawn, yield_now};
+use std::{mem, ptr};
 
-use crossbeam::channel::{self, Sender};
-use once_cell::sync::Lazy;
-use parking_lot::Mutex;
+use crossbeam::channel::{self, RecvTimeoutError, Sender};
+use crossbeam::queue::SegQueue;
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::{Mutex, MutexGuard, RwLock};
 
 use crate::collector::alloc::GcAllocation;
 use crate::collector::dropper::{BackgroundDropper, DropMessage};
+use crate::collector::local::LocalGarbage;
+use crate::collector::refcount::is_freeing;
+use crate::collector::schedule::ScheduleState;
+use crate::collector::sweep::PendingSweep;
 use crate::collector::trigger::GcTrigger;
 use crate::concurrency::atomic_protection::{APSInclusiveGuard, AtomicProtectingSpinlock};
 use crate::concurrency::chunked_ll::{CLLItem, ChunkedLinkedList};
@@ -22,7 +37,13 @@ use crate::concurrency::lockout::{ExclusiveWarrant, Lockout, Warrant};
 use crate::marker::GcDrop;
 use crate::{Finalize, Scan, ToScan};
 
+pub use crate::collector::alloc::{set_allocator, AllocError, SetAllocatorError};
+pub use crate::collector::backlog::PendingDestructionLimit;
 pub use crate::collector::data::{GcData, GcHandle, UnderlyingData};
+pub use crate::collector::parallelism::GcParallelism;
+pub use crate::collector::pause::GcPauseGuard;
+pub use crate::collector::schedule::GcSchedule;
+pub use crate::collector::trigger::GcPacingGoal;
 
 /// Intermediate struct. `Gc<T>` holds a `InternalGcRef`, which references a `GcHandle`
 /// There should be one `GcHandle` per `Gc<T>`
@@ -52,18 +73,47 @@ impl InternalGcRef {
 /// We don't want to expose what specific warrant provider we're using
 /// (this struct should be optimized away)
 pub struct GcGuardWarrant {
-    /// stores the internal warrant. only the drop being run is relevant
+    /// stores the internal warrant. mostly only the drop being run is relevant
     _warrant: Warrant<Arc<GcData>>,
 }
 type GcExclusiveWarrant = ExclusiveWarrant<Arc<GcData>>;
 
+/// Like `GcGuardWarrant`, but no-one else (including the collector) can hold a warrant at the same
+/// time, so the data can be changed
+pub struct GcExclusiveGuardWarrant {
+    _warrant: GcExclusiveWarrant,
+}
+
 pub struct Collector {
     /// shredder only allows one collection to proceed at a time
     gc_lock: Mutex<()>,
-    /// this prevents atomic operations from happening during collection time
+    /// the garbage the last collection found, if it hasn't all been swept up yet (see `sweep.rs`)
+    sweep: Mutex<Option<PendingSweep>>,
+    /// atomic operations hold this (inclusively) for their duration, so the collector can start and
+    /// stop marking without an atomic operation being halfway done
     atomic_spinlock: AtomicProtectingSpinlock,
+    /// set while a collection is marking, at which point atomic operations must `shade` data
+    marking: AtomicBool,
+    /// data shaded by atomic operations, which the current collection still has to mark
+    shaded: SegQueue<Arc<GcData>>,
     /// trigger decides when we should run a collection
     trigger: GcTrigger,
+    /// how many `GcPauseGuard`s are alive (automatic collection waits until there are none)
+    pauses: AtomicUsize,
+    /// set if the trigger asked for a collection while collection was paused
+    collection_deferred: AtomicBool,
+    /// when the async gc thread should collect on its own (see `schedule.rs`)
+    schedule: Mutex<GcSchedule>,
+    /// set by `report_idle`, until the async gc thread gets around to collecting
+    idle_reported: AtomicBool,
+    /// when set, data is freed as soon as its last handle is dropped
+    reference_counting: AtomicBool,
+    /// data whose last handle was dropped, waiting to be freed (see `refcount.rs`)
+    unreferenced: SegQueue<Arc<GcData>>,
+    /// when set, garbage is destroyed referrers first (see `order.rs`)
+    ordered_destruction: AtomicBool,
+    /// how collection and dropping work is spread across threads
+    parallelism: RwLock<GcParallelism>,
     /// dropping happens in a background thread. This struct lets us communicate with that thread
     dropper: BackgroundDropper,
     /// we run automatic gc in a background thread
@@ -99,8 +149,19 @@ impl Collector {
 
         let res = Arc::new(Self {
             gc_lock: Mutex::default(),
+            sweep: Mutex::default(),
             atomic_spinlock: AtomicProtectingSpinlock::default(),
+            marking: AtomicBool::new(false),
+            shaded: SegQueue::new(),
             trigger: GcTrigger::default(),
+            pauses: AtomicUsize::new(0),
+            collection_deferred: AtomicBool::new(false),
+            schedule: Mutex::default(),
+            idle_reported: AtomicBool::new(false),
+            reference_counting: AtomicBool::new(false),
+            unreferenced: SegQueue::new(),
+            ordered_destruction: AtomicBool::new(false),
+            parallelism: RwLock::default(),
             dropper: BackgroundDropper::new(),
             async_gc_notifier,
             tracked_data: TrackedData {
@@ -119,11 +180,32 @@ impl Collector {
         // The async Gc thread deals with background Gc'ing
         let async_collector_ref = Arc::downgrade(&res);
         spawn(move || {
-            // An Err value means the stream will never recover
-            while async_gc_receiver.recv().is_ok() {
-                if let Some(collector) = async_collector_ref.upgrade() {
+            let mut schedule_state = ScheduleState::new();
+            let mut wait = None;
+            loop {
+                // An Err value means the stream will never recover (unless we just timed out)
+                let notified = match wait {
+                    Some(timeout) => match async_gc_receiver.recv_timeout(timeout) {
+                        Ok(()) => true,
+                        Err(RecvTimeoutError::Timeout) => false,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    },
+                    None => match async_gc_receiver.recv() {
+                        Ok(()) => true,
+                        Err(_) => return,
+                    },
+                };
+
+                let Some(collector) = async_collector_ref.upgrade() else {
+                    wait = None;
+                    continue;
+                };
+                if notified {
+                    schedule_state.notified();
+                    collector.sweep_in_background();
                     collector.check_then_collect();
                 }
+                wait = collector.follow_schedule(&mut schedule_state);
             }
         });
 
@@ -149,6 +231,17 @@ impl Collector {
         (self.track(gc_data_ptr), heap_ptr)
     }
 
+    /// Like `track_with_drop`, but gives back an error if we're out of memory (see `allocate_or_collect`)
+    pub fn try_track_with_drop<T: Scan + GcDrop>(
+        &self,
+        data: T,
+    ) -> Result<(InternalGcRef, *const T), AllocError> {
+        let (gc_data_ptr, uninit_ptr) =
+            self.allocate_or_collect(GcAllocation::try_allocate_uninitialized_with_drop::<T>)?;
+        unsafe { ptr::write(uninit_ptr.cast_mut(), data) };
+        Ok((self.track(gc_data_ptr), uninit_ptr))
+    }
+
     pub fn track_with_no_drop<T: Scan>(&self, data: T) -> (InternalGcRef, *const T) {
         let (gc_data_ptr, heap_ptr) = GcAllocation::allocate_no_drop(data);
         (self.track(gc_data_ptr), heap_ptr)
@@ -175,15 +268,35 @@ impl Collector {
         T: Scan + GcDrop,
         F: FnOnce(InternalGcRef, *const T) -> T,
     {
-        let (gc_data_ptr, uninit_ptr) = GcAllocation::allocate_uninitialized_with_drop();
-        let (token, reference) = self.setup_gc_reference(gc_data_ptr);
+        let allocation = GcAllocation::allocate_uninitialized_with_drop();
+        self.track_uninitialized(allocation, init_function)
+    }
 
-        let t = init_function(self.clone_handle(&reference), uninit_ptr);
-        ptr::write(uninit_ptr as *mut T, t);
-        let init_ptr = uninit_ptr;
+    /// Like `track_with_initializer`, but gives back an error if we're out of memory (in which case
+    /// `init_function` is never run)
+    pub unsafe fn try_track_with_initializer<T, F>(
+        &self,
+        init_function: F,
+    ) -> Result<(InternalGcRef, *const T), AllocError>
+    where
+        T: Scan + GcDrop,
+        F: FnOnce(InternalGcRef, *const T) -> T,
+    {
+        let allocation =
+            self.allocate_or_collect(GcAllocation::try_allocate_uninitialized_with_drop)?;
+        Ok(self.track_uninitialized(allocation, init_function))
+    }
 
-        self.track_from_token(token);
-        (reference, init_ptr)
+    pub unsafe fn track_with_initializer_no_drop<T, F>(
+        &self,
+        init_function: F,
+    ) -> (InternalGcRef, *const T)
+    where
+        T: Scan,
+        F: FnOnce(InternalGcRef, *const T) -> T,
+    {
+        let allocation = GcAllocation::allocate_uninitialized_no_drop();
+        self.track_uninitialized(allocation, init_function)
     }
 
     pub unsafe fn track_with_initializer_and_finalize<T, F>(
@@ -194,23 +307,109 @@ impl Collector {
         T: Finalize + Scan,
         F: FnOnce(InternalGcRef, *const T) -> T,
     {
-        let (gc_data_ptr, uninit_ptr) = GcAllocation::allocate_uninitialized_with_finalization();
-        let (token, reference) = self.setup_gc_reference(gc_data_ptr);
+        let allocation = GcAllocation::allocate_uninitialized_with_finalization();
+        self.track_uninitialized(allocation, init_function)
+    }
 
-        let t = init_function(self.clone_handle(&reference), uninit_ptr);
-        ptr::write(uninit_ptr as *mut T, t);
-        let init_ptr = uninit_ptr;
+    /// Like `track_with_initializer`, but the initializer may fail. In that case the memory is
+    /// given back, and any handles that escaped the initializer will see the data as deallocated
+    pub unsafe fn track_with_fallible_initializer<T, E, F>(
+        &self,
+        init_function: F,
+    ) -> Result<(InternalGcRef, *const T), E>
+    where
+        T: Scan + GcDrop,
+        F: FnOnce(InternalGcRef, *const T) -> Result<T, E>,
+    {
+        let allocation = GcAllocation::allocate_uninitialized_with_drop();
+        self.try_track_uninitialized(allocation, init_function)
+    }
 
-        self.track_from_token(token);
-        (reference, init_ptr)
+    unsafe fn track_uninitialized<T, F>(
+        &self,
+        allocation: (GcAllocation, *const T),
+        init_function: F,
+    ) -> (InternalGcRef, *const T)
+    where
+        T: Scan,
+        F: FnOnce(InternalGcRef, *const T) -> T,
+    {
+        let res: Result<_, Infallible> =
+            self.try_track_uninitialized(allocation, |gc_ref, ptr| Ok(init_function(gc_ref, ptr)));
+
+        match res {
+            Ok(tracked) => tracked,
+            Err(never) => match never {},
+        }
     }
 
-    fn setup_gc_reference(&self, gc_data_ptr: GcAllocation) -> (TrackingSetupToken, InternalGcRef) {
+    /// Initialize and track an uninitialized allocation (one of `GcAllocation::allocate_uninitialized_*`)
+    unsafe fn try_track_uninitialized<T, E, F>(
+        &self,
+        (gc_data_ptr, uninit_ptr): (GcAllocation, *const T),
+        init_function: F,
+    ) -> Result<(InternalGcRef, *const T), E>
+    where
+        T: Scan,
+        F: FnOnce(InternalGcRef, *const T) -> Result<T, E>,
+    {
+        let (token, reference) = self.setup_gc_reference(gc_data_ptr, None);
+
+        // Handles may escape the initializer, but they can't be used until the data is initialized
+        let data = &token.data_to_track;
+        data.deallocated.store(true, Ordering::SeqCst);
+
+        match init_function(self.clone_handle(&reference), uninit_ptr) {
+            Ok(t) => {
+                ptr::write(uninit_ptr.cast_mut(), t);
+                let init_ptr = uninit_ptr;
+
+                // Release, so anyone who sees the data is initialized also sees the value we wrote
+                data.deallocated.store(false, Ordering::Release);
+
+                self.track_from_token(token);
+                Ok((reference, init_ptr))
+            }
+            Err(e) => {
+                // The data was never tracked, so the collector will never try to scan or free it
+                let data = token.data_to_track;
+                self.drop_handle(&reference);
+
+                data.underlying_allocation.release_memory::<T>();
+                Err(e)
+            }
+        }
+    }
+
+    /// Run `allocate`, and if we're out of memory, collect and try again
+    ///
+    /// We wait for the destructors to run after collecting, since that's when memory is given back.
+    fn allocate_or_collect<R>(
+        &self,
+        allocate: impl Fn() -> Result<R, AllocError>,
+    ) -> Result<R, AllocError> {
+        allocate().or_else(|_| {
+            self.collect();
+            self.synchronize_destructors();
+            allocate()
+        })
+    }
+
+    fn setup_gc_reference(
+        &self,
+        gc_data_ptr: GcAllocation,
+        home: Option<Weak<LocalGarbage>>,
+    ) -> (TrackingSetupToken, InternalGcRef) {
         let new_data_arc = Arc::new(GcData {
             underlying_allocation: gc_data_ptr,
             lockout: Lockout::new(),
             deallocated: AtomicBool::new(false),
+            awaiting_destruction: AtomicBool::new(false),
             last_marked: AtomicU64::new(0),
+            handle_count: AtomicUsize::new(1),
+            in_atomic: AtomicBool::new(false),
+            tracked_slot: OnceCell::new(),
+            home,
         });
 
         let new_handle_arc = Arc::new(GcHandle {
@@ -230,39 +429,56 @@ impl Collector {
     }
 
     fn track_from_token(&self, token: TrackingSetupToken) {
-        self.tracked_data.data.insert(token.data_to_track);
+        // If the drop thread has fallen behind, wait for it to catch up. (Unless we're a destructor,
+        // since the drop thread may be waiting on us)
+        if !is_freeing() {
+            self.dropper.backlog().wait_for_room();
+        }
+
+        // Allocations help sweep up after the last collection
+        self.sweep_step();
+
+        let data = token.data_to_track;
+        let tracked = self.tracked_data.data.insert(data.clone());
+        // This is the only place the slot is set, so it can't already be set
+        let _ = data.tracked_slot.set(tracked.slot());
 
         // When we allocate, the heuristic for whether we need to GC might change
         self.notify_async_gc_thread();
+
+        // Allocations also destroy whatever garbage has been sent back to this thread
+        self.run_local_destructors();
     }
 
     fn track(&self, gc_data_ptr: GcAllocation) -> InternalGcRef {
-        let (tracking_token, reference) = self.setup_gc_reference(gc_data_ptr);
+        let (tracking_token, reference) = self.setup_gc_reference(gc_data_ptr, None);
         self.track_from_token(tracking_token);
         reference
     }
 
     pub fn drop_handle(&self, handle: &InternalGcRef) {
-        self.tracked_data.handles.remove(&handle.handle_ref);
+        // Handles can be invalidated twice (by a finalizer, for instance), but only count once
+        if !self.tracked_data.handles.remove(&handle.handle_ref) {
+            return;
+        }
+
+        if let UnderlyingData::Fixed(data) = &handle.handle_ref.v.underlying_data {
+            if data.handle_count.fetch_sub(1, Ordering::SeqCst) == 1 {
+                self.data_unreferenced(data);
+            }
+        }
 
         // NOTE: This is worth experimenting with
         // self.notify_async_gc_thread();
     }
 
     pub fn clone_handle(&self, handle: &InternalGcRef) -> InternalGcRef {
-        let new_handle_arc = Arc::new(GcHandle {
-            underlying_data: UnderlyingData::Fixed(handle.data().clone()),
-            last_non_rooted: AtomicU64::new(0),
-        });
-
-        let new_handle = self.tracked_data.handles.insert(new_handle_arc);
-
-        InternalGcRef {
-            handle_ref: new_handle,
-        }
+        self.handle_from_data(handle.data().clone())
     }
 
     pub fn handle_from_data(&self, underlying_data: Arc<GcData>) -> InternalGcRef {
+        underlying_data.handle_count.fetch_add(1, Ordering::SeqCst);
+
         let new_handle_arc = Arc::new(GcHandle {
             underlying_data: UnderlyingData::Fixed(underlying_data),
             last_non_rooted: AtomicU64::new(0),
@@ -288,23 +504,202 @@ impl Collector {
         }
     }
 
+    /// Figure out what data is behind a handle. For atomic handles this is a snapshot (which may be
+    /// `None`, if the atomic is null)
+    pub fn resolve_data(&self, handle: &InternalGcRef) -> Option<Arc<GcData>> {
+        match &handle.handle_ref.v.underlying_data {
+            UnderlyingData::Fixed(data) => Some(data.clone()),
+            UnderlyingData::DynamicForAtomic(atomic_ptr) => {
+                self.load_atomic(atomic_ptr, Ordering::SeqCst)
+            }
+        }
+    }
+
+    /// Scan some data outside of a collection, holding a warrant so it can't change underneath us
+    ///
+    /// Returns `false` (without scanning) if the data is not in a scannable state
+    #[allow(clippy::unused_self)]
+    pub fn scan_data<F: FnMut(InternalGcRef)>(&self, data: &Arc<GcData>, callback: F) -> bool {
+        // Data is marked as deallocated before anyone takes the exclusive warrant to destroy (or
+        // move) it, so we must check after getting our warrant
+        let _warrant = Lockout::get_warrant(data.clone());
+
+        // Data being initialized or destroyed is marked as deallocated, and may not be scanned
+        if data.deallocated.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        data.underlying_allocation.scan(callback);
+        true
+    }
+
+    /// Is `handle` the only handle pointing to its data?
+    ///
+    /// Handles inside garbage that hasn't been collected yet count too. Since a new handle can only
+    /// be made from an existing one, the answer stays `true` for as long as the caller has
+    /// exclusive access to `handle`.
+    pub fn is_unique(&self, handle: &InternalGcRef) -> bool {
+        let data = handle.data();
+
+        // Data that's still being initialized is marked as deallocated
+        if data.deallocated.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        // Usually the handle count is all we need, and we don't have to take any locks
+        if !data.in_atomic.load(Ordering::SeqCst) {
+            return data.handle_count.load(Ordering::SeqCst) == 1;
+        }
+
+        let Some(_gc_guard) = self.lock_gc_unless_freeing() else {
+            return false;
+        };
+        unsafe { self.count_handles_to(data) == 1 }
+    }
+
+    /// Stop tracking the data behind `handle`, if `handle` is the only handle pointing to it
+    ///
+    /// On success the collector forgets about the data entirely, so the caller becomes responsible
+    /// for the value inside it and for giving back its memory. This fails if the data is in use, if
+    /// any other handle (including an atomic one) points to it, or if it is still being
+    /// initialized. Handles inside garbage that hasn't been collected yet count too.
+    pub fn try_untrack_unique(&self, handle: &InternalGcRef) -> bool {
+        let data = handle.data();
+
+        // Data that's still being initialized is marked as deallocated
+        if data.deallocated.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        // Block collection, so the data can't be swept or scanned while we untrack it
+        let Some(gc_guard) = self.lock_gc_unless_freeing() else {
+            return false;
+        };
+
+        // If we can get an exclusive warrant, no-one is looking at the data right now
+        let Some(warrant) = Lockout::get_exclusive_warrant(data.clone()) else {
+            return false;
+        };
+
+        if unsafe { self.count_handles_to(data) } != 1 {
+            return false;
+        }
+
+        // Claim the data, so anyone who still has an `Arc` to it (like a graph walk) won't scan it
+        if data.deallocated.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        // (Data is always tracked once initialized)
+        if let Some(slot) = data.tracked_slot.get() {
+            self.tracked_data.data.remove_from_slot(*slot, data);
+        }
+
+        drop(warrant);
+        drop(gc_guard);
+        true
+    }
+
+    /// Take the `gc_lock`, unless we're running destructors and someone else has it
+    ///
+    /// A collection may be waiting on the destructors, so they must never wait for the lock.
+    fn lock_gc_unless_freeing(&self) -> Option<MutexGuard<'_, ()>> {
+        if is_freeing() {
+            self.gc_lock.try_lock()
+        } else {
+            Some(self.gc_lock.lock())
+        }
+    }
+
+    /// Count every handle (including atomic ones) pointing to `data`
+    ///
+    /// Handles count themselves on their data, except atomic ones. So unless the data has ever been
+    /// in an atomic, that count is exact. Otherwise we look at every handle, with atomic operations
+    /// blocked so nothing moves around while we count.
+    ///
+    /// Only safe to call while holding the `gc_lock`
+    unsafe fn count_handles_to(&self, data: &Arc<GcData>) -> usize {
+        if !data.in_atomic.load(Ordering::SeqCst) {
+            return data.handle_count.load(Ordering::SeqCst);
+        }
+
+        let _atomic_spinlock_guard = self.atomic_spinlock.lock_exclusive();
+        let handle_count = AtomicUsize::new(0);
+        let handles = &self.tracked_data.handles;
+        self.parallelism()
+            .for_each_tracked(handles, |other_handle| {
+                // Safe, since atomics can't be modified while the spinlock is held
+                other_handle.underlying_data.with_data(|other_data| {
+                    if ptr::eq(other_data, Arc::as_ptr(data)) {
+                        handle_count.fetch_add(1, Ordering::SeqCst);
+                    }
+                });
+            });
+
+        handle_count.load(Ordering::SeqCst)
+    }
+
+    /// The write barrier, run whenever a `GcCell` has been mutated
+    ///
+    /// `warrant` is the warrant protecting the data the cell lives in, if we know it. Right now
+    /// nothing needs to hear about writes: marking holds a warrant on everything it scans, so a
+    /// cell can't change underneath it. This is the hook for a collector that does (like an
+    /// incremental or generational one).
+    #[inline]
+    #[allow(clippy::unused_self)]
+    pub fn write_barrier(&self, _warrant: Option<&GcGuardWarrant>) {}
+
     #[allow(clippy::unused_self)]
     pub fn get_data_warrant(&self, handle: &InternalGcRef) -> GcGuardWarrant {
         // This check is only necessary in the destructors
         // The destructor thread will always set the `deallocated` flag before deallocating data
         if let UnderlyingData::Fixed(fixed) = &handle.handle_ref.v.underlying_data {
-            let data_deallocated = fixed.deallocated.load(Ordering::SeqCst);
+            // Garbage destroyed in order can be looked at until its turn comes. Its turn waits for
+            // anyone holding a warrant, so we must check after getting ours
+            let warrant = Lockout::get_warrant(fixed.clone());
+            let data_deallocated = fixed.deallocated.load(Ordering::SeqCst)
+                && !fixed.awaiting_destruction.load(Ordering::SeqCst);
 
             assert!(!data_deallocated, "Tried to access into a Gc, but the internal state was corrupted (perhaps you're manipulating Gc<?> in a destructor?)");
 
-            GcGuardWarrant {
-                _warrant: Lockout::get_warrant(fixed.clone()),
-            }
+            GcGuardWarrant { _warrant: warrant }
         } else {
             panic!("Cannot get data warrant for atomic data!")
         }
     }
 
+    /// Get an exclusive warrant for the data behind `handle`, or `None` if someone else (like a
+    /// running collection, or `visit_graph`) holds a warrant for it right now
+    #[allow(clippy::unused_self)]
+    pub fn try_get_exclusive_data_warrant(
+        &self,
+        handle: &InternalGcRef,
+    ) -> Option<GcExclusiveGuardWarrant> {
+        let data = handle.data();
+        let warrant = Lockout::get_exclusive_warrant(data.clone())?;
+        Self::assert_not_deallocated(data);
+        Some(GcExclusiveGuardWarrant { _warrant: warrant })
+    }
+
+    /// Get an exclusive warrant for the data behind `handle`, waiting for everyone else's warrants
+    ///
+    /// Only use this for data nothing else can reach, where only a collection can be in the way.
+    #[allow(clippy::unused_self)]
+    pub fn wait_for_exclusive_data_warrant(
+        &self,
+        handle: &InternalGcRef,
+    ) -> GcExclusiveGuardWarrant {
+        let data = handle.data();
+        let warrant = Lockout::wait_for_exclusive_warrant(data.clone());
+        Self::assert_not_deallocated(data);
+        GcExclusiveGuardWarrant { _warrant: warrant }
+    }
+
+    fn assert_not_deallocated(data: &GcData) {
+        let data_deallocated = data.deallocated.load(Ordering::SeqCst)
+            && !data.awaiting_destruction.load(Ordering::SeqCst);
+        assert!(!data_deallocated, "Tried to access into a Gc, but the internal state was corrupted (perhaps you're manipulating Gc<?> in a destructor?)");
+    }
+
     pub fn tracked_data_count(&self) -> usize {
         self.tracked_data.data.estimate_len()
     }
@@ -313,11 +708,51 @@ impl Collector {
         self.tracked_data.handles.estimate_len()
     }
 
+    pub fn pending_destruction_count(&self) -> usize {
+        self.dropper.backlog().objects()
+    }
+
+    pub fn pending_destruction_bytes(&self) -> usize {
+        self.dropper.backlog().bytes()
+    }
+
+    pub fn set_pending_destruction_limit(&self, limit: PendingDestructionLimit) {
+        self.dropper.backlog().set_limit(limit);
+    }
+
+    pub fn set_parallelism(&self, parallelism: GcParallelism) {
+        *self.parallelism.write() = parallelism;
+    }
+
+    /// The current `GcParallelism`. (Work should stick with one, even if it's changed partway.)
+    fn parallelism(&self) -> GcParallelism {
+        self.parallelism.read().clone()
+    }
+
+    pub fn set_reference_counting(&self, enabled: bool) {
+        self.reference_counting.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn set_ordered_destruction(&self, enabled: bool) {
+        self.ordered_destruction.store(enabled, Ordering::SeqCst);
+    }
+
     pub fn set_gc_trigger_percent(&self, new_trigger_percent: f32) {
         self.trigger.set_trigger_percent(new_trigger_percent);
     }
 
+    pub fn gc_trigger_percent(&self) -> f32 {
+        self.trigger.trigger_percent()
+    }
+
+    pub fn set_pacing_goal(&self, goal: Option<GcPacingGoal>) {
+        self.trigger.set_pacing_goal(goal);
+    }
+
     pub fn synchronize_destructors(&self) {
+        // Garbage that hasn't been swept yet hasn't made it to the drop thread
+        self.finish_sweep();
+
         // We send a channel to the drop thread and wait for it to respond
         // This has the effect of synchronizing this thread with the drop thread
 
@@ -331,18 +766,105 @@ impl Collector {
         receiver.recv().expect("drop thread should be infallible!");
     }
 
+    /// Block (briefly) if a collection is starting or finishing marking
     #[inline]
-    pub fn get_collection_blocker_spinlock(&self) -> APSInclusiveGuard<'_> {
+    fn atomic_operation_guard(&self) -> APSInclusiveGuard<'_> {
         loop {
             if let Some(inclusive_guard) = self.atomic_spinlock.lock_inclusive() {
                 return inclusive_guard;
             }
-            // block on the collector if we can't get the APS guard
-            let collector_block = self.gc_lock.lock();
-            drop(collector_block);
+            // The collector only holds the lock exclusively for a moment, so just wait it out
+            yield_now();
+        }
+    }
+
+    /// The deletion barrier, run by atomic operations on any data they read or overwrite
+    ///
+    /// While a collection is marking, the data is queued up to be marked too. This is a
+    /// snapshot-at-the-beginning scheme: anything reachable when marking started stays alive, even
+    /// if the only path to it was through an atomic that has since changed. Loads are included,
+    /// since the new handle they create may be missed when the collector looks for roots.
+    ///
+    /// Only call this while holding an `atomic_operation_guard`
+    fn shade(&self, data_ptr: *const GcData) {
+        if self.marking.load(Ordering::SeqCst) {
+            if let Some(data) = unsafe { data_from_atomic_ptr(data_ptr) } {
+                self.shaded.push(data);
+            }
+        }
+    }
+
+    /// Load the data an atomic points to (if any)
+    pub fn load_atomic(
+        &self,
+        atomic_ptr: &AtomicPtr<GcData>,
+        ordering: Ordering,
+    ) -> Option<Arc<GcData>> {
+        let _guard = self.atomic_operation_guard();
+
+        let data_ptr = atomic_ptr.load(ordering);
+        self.shade(data_ptr);
+
+        // Safe, since we know this atomic is reachable, so its data is either marked or new
+        // (otherwise someone would be accessing an `AtomicGc` pointing to freed data--which is
+        // impossible in safe code.) The barrier keeps that true while a collection is running.
+        unsafe { data_from_atomic_ptr(data_ptr) }
+    }
+
+    /// Store `new_data_ptr` into an atomic
+    pub fn store_atomic(
+        &self,
+        atomic_ptr: &AtomicPtr<GcData>,
+        new_data_ptr: *mut GcData,
+        ordering: Ordering,
+    ) {
+        let _guard = self.atomic_operation_guard();
+
+        if self.marking.load(Ordering::SeqCst) {
+            // We need to know what we overwrote, so this has to be a swap
+            let old_data_ptr = atomic_ptr.swap(new_data_ptr, ordering);
+            self.shade(old_data_ptr);
+        } else {
+            atomic_ptr.store(new_data_ptr, ordering);
         }
     }
 
+    /// Swap `new_data_ptr` into an atomic, returning the data it used to point to (if any)
+    pub fn swap_atomic(
+        &self,
+        atomic_ptr: &AtomicPtr<GcData>,
+        new_data_ptr: *mut GcData,
+        ordering: Ordering,
+    ) -> Option<Arc<GcData>> {
+        let _guard = self.atomic_operation_guard();
+
+        let old_data_ptr = atomic_ptr.swap(new_data_ptr, ordering);
+        self.shade(old_data_ptr);
+
+        // Safe for the same reasons as in `load_atomic`
+        unsafe { data_from_atomic_ptr(old_data_ptr) }
+    }
+
+    /// Compare and exchange the pointer in an atomic, returning whether the exchange happened
+    pub fn compare_exchange_atomic(
+        &self,
+        atomic_ptr: &AtomicPtr<GcData>,
+        current_data_ptr: *mut GcData,
+        new_data_ptr: *mut GcData,
+        success: Ordering,
+        failure: Ordering,
+    ) -> bool {
+        let _guard = self.atomic_operation_guard();
+
+        let swap_result =
+            atomic_ptr.compare_exchange(current_data_ptr, new_data_ptr, success, failure);
+        if swap_result.is_ok() {
+            self.shade(current_data_ptr);
+        }
+
+        swap_result.is_ok()
+    }
+
     pub fn check_then_collect(&self) -> bool {
         let gc_guard = self.gc_lock.lock();
 
@@ -351,6 +873,7 @@ impl Collector {
         if self
             .trigger
             .should_collect(current_data_count, current_handle_count)
+            && !self.defer_collection()
         {
             self.do_collect(gc_guard);
             true
@@ -362,11 +885,32 @@ impl Collector {
     pub fn collect(&self) {
         let gc_guard = self.gc_lock.lock();
         self.do_collect(gc_guard);
+
+        // Someone asking for a collection wants the garbage gone, so sweep it all up now (the
+        // `gc_lock` is free by now, so this doesn't hold anyone up)
+        self.finish_sweep();
     }
 }
 
 pub static COLLECTOR: Lazy<Arc<Collector>> = Lazy::new(Collector::new);
 
+/// Make a new `Arc` to the data behind a pointer read out of an atomic (or `None` if it's null)
+///
+/// Only safe if the data is known to be live
+unsafe fn data_from_atomic_ptr(data_ptr: *const GcData) -> Option<Arc<GcData>> {
+    if data_ptr.is_null() {
+        return None;
+    }
+
+    // Create a new `Arc` pointing to the same data, but don't invalidate the existing `Arc`
+    // (which is effectively "behind" the pointer)
+    let data_temp = Arc::from_raw(data_ptr);
+    let data = data_temp.clone();
+    mem::forget(data_temp);
+
+    Some(data)
+}
+
 #[cfg(test)]
 pub(crate) fn get_mock_handle() -> InternalGcRef {
     use crate::marker::GcSafe;
@@ -389,7 +933,12 @@ pub(crate) fn get_mock_handle() -> InternalGcRef {
             underlying_allocation: unsafe { GcAllocation::raw(Box::into_raw(mock_scannable)) },
             lockout: Lockout::new(),
             deallocated: AtomicBool::new(false),
+            awaiting_destruction: AtomicBool::new(false),
             last_marked: AtomicU64::new(0),
+            handle_count: AtomicUsize::new(1),
+            in_atomic: AtomicBool::new(false),
+            tracked_slot: OnceCell::new(),
+            home: None,
         })),
         last_non_rooted: AtomicU64::new(0),
     });
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::collector::{GcData, InternalGcRef, UnderlyingData};
+
+/// Put a batch of garbage in the order it should be destroyed: referrers before referents
+///
+/// Data in a cycle refers (indirectly) to itself, so there's no right order for it. A cycle is
+/// destroyed as a group, after everything pointing into it and before everything it points to,
+/// but its members are destroyed in no particular order.
+///
+/// This finds the cycles (strongly connected components) with Tarjan's algorithm, which produces
+/// them referents first. The algorithm is usually recursive, but garbage can be a long chain, so
+/// this keeps its own stack.
+///
+/// Only safe to call on garbage that no-one has started destroying yet
+pub(super) unsafe fn destruction_order(garbage: Vec<Arc<GcData>>) -> Vec<Arc<GcData>> {
+    let positions: HashMap<*const GcData, usize> = garbage
+        .iter()
+        .enumerate()
+        .map(|(i, data)| (Arc::as_ptr(data), i))
+        .collect();
+
+    // Only the edges between pieces of garbage matter, everything else is still alive
+    let edges: Vec<Vec<usize>> = garbage
+        .iter()
+        .map(|data| {
+            let mut edges = Vec::new();
+            data.underlying_allocation.scan(|h: InternalGcRef| {
+                if let Some(&to) = positions.get(&referent(&h)) {
+                    edges.push(to);
+                }
+            });
+            edges
+        })
+        .collect();
+
+    let mut tarjan = Tarjan::new(garbage.len());
+    for root in 0..garbage.len() {
+        tarjan.visit_from(root, &edges);
+    }
+
+    let mut garbage: Vec<_> = garbage.into_iter().map(Some).collect();
+    tarjan
+        .finished
+        .iter()
+        .rev()
+        .filter_map(|&i| garbage[i].take())
+        .collect()
+}
+
+/// What data does this handle point to? (For an atomic, what it points to right now)
+fn referent(handle: &InternalGcRef) -> *const GcData {
+    match &handle.handle_ref.v.underlying_data {
+        UnderlyingData::Fixed(data) => Arc::as_ptr(data),
+        UnderlyingData::DynamicForAtomic(ptr) => ptr.load(Ordering::SeqCst).cast_const(),
+    }
+}
+
+const UNVISITED: usize = usize::MAX;
+
+struct Tarjan {
+    /// the order each vertex was first visited in (or `UNVISITED`)
+    index: Vec<usize>,
+    /// the earliest visited vertex on the stack this vertex can reach
+    low_link: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    /// vertices whose component is finished, in the order the components finished
+    finished: Vec<usize>,
+}
+
+impl Tarjan {
+    fn new(vertices: usize) -> Self {
+        Self {
+            index: vec![UNVISITED; vertices],
+            low_link: vec![UNVISITED; vertices],
+            on_stack: vec![false; vertices],
+            stack: Vec::new(),
+            next_index: 0,
+            finished: Vec::with_capacity(vertices),
+        }
+    }
+
+    fn visit(&mut self, v: usize) {
+        self.index[v] = self.next_index;
+        self.low_link[v] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(v);
+        self.on_stack[v] = true;
+    }
+
+    fn visit_from(&mut self, root: usize, edges: &[Vec<usize>]) {
+        if self.index[root] != UNVISITED {
+            return;
+        }
+
+        // Each frame is a vertex, and how many of its edges we've followed so far
+        self.visit(root);
+        let mut frames = vec![(root, 0)];
+
+        while let Some((v, followed)) = frames.last_mut() {
+            let v = *v;
+            if let Some(&w) = edges[v].get(*followed) {
+                *followed += 1;
+                if self.index[w] == UNVISITED {
+                    self.visit(w);
+                    frames.push((w, 0));
+                } else if self.on_stack[w] {
+                    self.low_link[v] = self.low_link[v].min(self.index[w]);
+                }
+                continue;
+            }
+
+            frames.pop();
+            if let Some(&(parent, _)) = frames.last() {
+                self.low_link[parent] = self.low_link[parent].min(self.low_link[v]);
+            }
+
+            // `v` is the first vertex we visited in its component, so the component is done
+            if self.low_link[v] == self.index[v] {
+                loop {
+                    let w = self.stack.pop().expect("v is still on the stack");
+                    self.on_stack[w] = false;
+                    self.finished.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
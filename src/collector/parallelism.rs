@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+use crate::concurrency::chunked_ll::ChunkedLinkedList;
+
+/// How `shredder` spreads out its work (collecting, and running destructors) across threads
+///
+/// Set this with `set_gc_parallelism`. The default is `GcParallelism::GlobalPool`.
+#[derive(Clone, Debug, Default)]
+pub enum GcParallelism {
+    /// Use rayon's global thread pool
+    #[default]
+    GlobalPool,
+    /// Use the given rayon thread pool
+    ///
+    /// This keeps garbage collection from competing with (or deadlocking against) work in your
+    /// other pools. `GcParallelism::private_pool` creates a pool just for `shredder`.
+    Pool(Arc<ThreadPool>),
+    /// Do all the work on one thread, without any thread pool
+    ///
+    /// Collection happens on the thread that runs it, and destructors run on the background drop
+    /// thread. This is best when there's only one core to go around.
+    Sequential,
+}
+
+impl GcParallelism {
+    /// Create a new thread pool with `num_threads` threads, just for garbage collection
+    ///
+    /// If `num_threads` is zero, rayon picks the number of threads (usually one per core).
+    ///
+    /// # Errors
+    /// Returns the `ThreadPoolBuildError` if the pool's threads couldn't be created.
+    pub fn private_pool(num_threads: usize) -> Result<Self, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|i| format!("shredder-gc-{i}"))
+            .build()?;
+
+        Ok(Self::Pool(Arc::new(pool)))
+    }
+
+    pub(crate) fn is_sequential(&self) -> bool {
+        matches!(self, Self::Sequential)
+    }
+
+    /// Run `f` (which may use rayon) in the right thread pool
+    pub(crate) fn install<R: Send, F: FnOnce() -> R + Send>(&self, f: F) -> R {
+        match self {
+            Self::Pool(pool) => pool.install(f),
+            Self::GlobalPool | Self::Sequential => f(),
+        }
+    }
+
+    /// Run `f` on every item in `list`
+    pub(crate) fn for_each_tracked<T, F>(&self, list: &ChunkedLinkedList<T>, f: F)
+    where
+        T: Send + Sync,
+        F: Fn(Arc<T>) + Sync + Send,
+    {
+        if self.is_sequential() {
+            list.for_each(f);
+        } else {
+            self.install(|| list.par_iter(f));
+        }
+    }
+
+    /// Run `f` on every item in `items`
+    pub(crate) fn for_each<T, F>(&self, items: &[T], f: F)
+    where
+        T: Sync,
+        F: Fn(&T) + Sync + Send,
+    {
+        if self.is_sequential() {
+            items.iter().for_each(f);
+        } else {
+            self.install(|| items.par_iter().for_each(f));
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::atomic::Ordering;
+
+use crate::collector::{Collector, COLLECTOR};
+
+/// While this is alive, `shredder` won't start collections automatically (see `pause_collection`)
+#[must_use = "collection resumes as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct GcPauseGuard {
+    _private: (),
+}
+
+impl Drop for GcPauseGuard {
+    fn drop(&mut self) {
+        COLLECTOR.resume_collection();
+    }
+}
+
+impl Collector {
+    pub fn pause_collection(&self) -> GcPauseGuard {
+        self.pauses.fetch_add(1, Ordering::SeqCst);
+        GcPauseGuard { _private: () }
+    }
+
+    fn resume_collection(&self) {
+        let last_pause = self.pauses.fetch_sub(1, Ordering::SeqCst) == 1;
+
+        // The async gc thread will check the trigger again, and collect if it still wants to
+        if last_pause && self.collection_deferred.swap(false, Ordering::SeqCst) {
+            self.notify_async_gc_thread();
+        }
+    }
+
+    /// Should an automatic collection be put off? If so, it's run once collection is resumed
+    pub(super) fn defer_collection(&self) -> bool {
+        if self.pauses.load(Ordering::SeqCst) == 0 {
+            return false;
+        }
+
+        self.collection_deferred.store(true, Ordering::SeqCst);
+
+        // If collection was resumed in the meantime, whoever resumed it may have missed that we
+        // wanted to collect. Then it's up to us
+        self.pauses.load(Ordering::SeqCst) > 0
+            || !self.collection_deferred.swap(false, Ordering::SeqCst)
+    }
+}
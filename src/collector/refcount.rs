@@ -0,0 +1,143 @@
+use std::cell::Cell;
+use std::panic::catch_unwind;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread::yield_now;
+
+use crate::collector::{Collector, GcData};
+use crate::concurrency::lockout::Lockout;
+
+thread_local! {
+    /// Is this thread running destructors for the collector right now?
+    static FREEING: Cell<bool> = const { Cell::new(false) };
+    /// Is this thread in the middle of `free_unreferenced`? (If so, more data is just queued up)
+    static FREEING_UNREFERENCED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f`, noting that any handles it drops are being dropped by a destructor
+///
+/// Destructors may be running for a collection (that's waiting for them), so while they run we
+/// must never block on the `gc_lock`.
+pub(crate) fn while_freeing<R, F: FnOnce() -> R>(f: F) -> R {
+    let was_freeing = FREEING.with(|freeing| freeing.replace(true));
+    let res = f();
+    FREEING.with(|freeing| freeing.set(was_freeing));
+    res
+}
+
+/// Is this thread running destructors for the collector right now? (See `while_freeing`)
+pub(crate) fn is_freeing() -> bool {
+    FREEING.with(Cell::get)
+}
+
+impl Collector {
+    /// Called when the last (non-atomic) handle to `data` has been dropped
+    pub(super) fn data_unreferenced(&self, data: &Arc<GcData>) {
+        if !self.reference_counting.load(Ordering::SeqCst) {
+            return;
+        }
+
+        // Reference counting can't see atomics, so it can't know if this is garbage
+        if data.in_atomic.load(Ordering::SeqCst) {
+            return;
+        }
+
+        // Data that is being initialized or destroyed is marked as deallocated
+        if data.deallocated.load(Ordering::SeqCst) {
+            return;
+        }
+
+        self.unreferenced.push(data.clone());
+        self.free_unreferenced();
+
+        // If that data (or anything it owned) belonged to this thread, it was sent back here
+        self.run_local_destructors();
+    }
+
+    /// Free all the data whose handle count has dropped to zero
+    ///
+    /// The data is claimed while holding the `gc_lock`, but destroyed after it's released, so
+    /// destructors are free to collect (or do anything else that takes the lock). If we're running
+    /// destructors (and so may be holding up a collection) this only frees data if the `gc_lock` is
+    /// free. Otherwise it's left for whoever holds the lock: every collection frees unreferenced
+    /// data before it's done.
+    ///
+    /// Destructors run here may make more data unreferenced. That's queued up for the loop here to
+    /// free (rather than freed recursively), so long chains of `Gc`s can't overflow the stack.
+    pub(super) fn free_unreferenced(&self) {
+        if FREEING_UNREFERENCED.with(Cell::get) {
+            return;
+        }
+        let freeing = is_freeing();
+
+        while !self.unreferenced.is_empty() {
+            let gc_guard = if freeing {
+                match self.gc_lock.try_lock() {
+                    Some(gc_guard) => gc_guard,
+                    None => return,
+                }
+            } else {
+                self.gc_lock.lock()
+            };
+
+            let claimed = self.claim_unreferenced();
+            drop(gc_guard);
+
+            FREEING_UNREFERENCED.with(|f| f.set(true));
+            while_freeing(|| {
+                for data in claimed {
+                    Self::destroy_unreferenced(&data);
+                }
+            });
+            FREEING_UNREFERENCED.with(|f| f.set(false));
+        }
+    }
+
+    /// Claim the data waiting in `unreferenced`, and stop tracking it
+    ///
+    /// Only safe to call while holding the `gc_lock` (outside of marking)
+    fn claim_unreferenced(&self) -> Vec<Arc<GcData>> {
+        let mut claimed = Vec::new();
+
+        while let Some(data) = self.unreferenced.pop() {
+            // If this is already set, a collection found this was garbage first
+            if data.deallocated.swap(true, Ordering::SeqCst) {
+                continue;
+            }
+
+            // Without any handles, only a graph walk could be looking at this data, and it will
+            // see the data is deallocated soon enough
+            let warrant = loop {
+                if let Some(warrant) = Lockout::get_exclusive_warrant(data.clone()) {
+                    break warrant;
+                }
+                yield_now();
+            };
+
+            // (Data is always tracked by the time its handle count can drop to zero)
+            if let Some(slot) = data.tracked_slot.get() {
+                self.tracked_data.data.remove_from_slot(*slot, &data);
+            }
+            drop(warrant);
+
+            claimed.push(data);
+        }
+
+        claimed
+    }
+
+    /// Run the destructor for claimed data (or send it home, if it has to be destroyed elsewhere)
+    fn destroy_unreferenced(data: &Arc<GcData>) {
+        if data.send_home() {
+            return;
+        }
+
+        let underlying_allocation = data.underlying_allocation;
+        let res = catch_unwind(move || unsafe {
+            underlying_allocation.deallocate();
+        });
+        if let Err(e) = res {
+            eprintln!("Gc drop failed: {e:?}");
+        }
+    }
+}
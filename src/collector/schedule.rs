@@ -0,0 +1,113 @@
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use crate::collector::Collector;
+
+/// When `shredder` should collect, besides when the trigger asks for it (see `set_gc_schedule`)
+///
+/// The default schedule is empty, so collection only happens when allocation triggers it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GcSchedule {
+    /// collect at least this often
+    pub interval: Option<Duration>,
+    /// collect once nothing has been allocated for this long
+    pub idle_after: Option<Duration>,
+}
+
+/// What the async gc thread remembers between wake ups, to follow the schedule
+#[derive(Debug)]
+pub(super) struct ScheduleState {
+    /// when the async gc thread was last notified (usually because of an allocation)
+    activity: Instant,
+    /// when we last checked if an interval's worth of changes needed collecting
+    interval_check: Instant,
+    /// when we last checked if the changes before an idle period needed collecting
+    idle_check: Instant,
+}
+
+impl ScheduleState {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            activity: now,
+            interval_check: now,
+            idle_check: now,
+        }
+    }
+
+    /// Note that the async gc thread was just notified
+    pub fn notified(&mut self) {
+        self.activity = Instant::now();
+    }
+}
+
+impl Collector {
+    pub fn set_schedule(&self, schedule: GcSchedule) {
+        *self.schedule.lock() = schedule;
+        // The async gc thread may be waiting on the old schedule
+        self.notify_async_gc_thread();
+    }
+
+    pub fn report_idle(&self) {
+        self.idle_reported.store(true, Ordering::SeqCst);
+        self.notify_async_gc_thread();
+    }
+
+    /// Collect if the schedule says it's time (or the program said it's idle), then return how long
+    /// the async gc thread can wait before it needs to check again (or `None` if it can wait for
+    /// an allocation)
+    pub(super) fn follow_schedule(&self, state: &mut ScheduleState) -> Option<Duration> {
+        let schedule = *self.schedule.lock();
+        let now = Instant::now();
+        let last_collection = self.trigger.last_collection_end();
+        let after_last_collection = |time: Instant| last_collection.map_or(time, |c| c.max(time));
+
+        let interval_due = schedule
+            .interval
+            .map(|interval| after_last_collection(state.interval_check) + interval);
+
+        // Idle periods only matter if something happened since we last collected (or checked)
+        let active_since = state.activity > after_last_collection(state.idle_check);
+        let idle_due = schedule
+            .idle_after
+            .filter(|_| active_since)
+            .map(|idle_after| state.activity + idle_after);
+
+        let interval_now = interval_due.is_some_and(|due| due <= now);
+        let idle_now = idle_due.is_some_and(|due| due <= now);
+        let reported_idle = self.idle_reported.swap(false, Ordering::SeqCst);
+
+        if interval_now || idle_now || reported_idle {
+            if interval_now {
+                state.interval_check = now;
+            }
+            if idle_now {
+                state.idle_check = now;
+            }
+            self.scheduled_collect();
+
+            // The collection moved the schedule along
+            return self.follow_schedule(state);
+        }
+
+        [interval_due, idle_due]
+            .iter()
+            .flatten()
+            .min()
+            .map(|&due| due - now)
+    }
+
+    /// Collect, unless there's nothing new to collect or collection is paused
+    fn scheduled_collect(&self) {
+        if self.pauses.load(Ordering::SeqCst) > 0 {
+            return;
+        }
+
+        let changed = self
+            .trigger
+            .changed_since_collection(self.tracked_data_count(), self.handle_count());
+        if changed {
+            self.collect();
+        }
+    }
+}
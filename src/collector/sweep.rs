@@ -0,0 +1,167 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::collector::dropper::DropMessage;
+use crate::collector::{Collector, GcData, GcParallelism};
+use crate::concurrency::chunked_ll::CLLCursor;
+
+/// A sweep that a collection has left to be done lazily
+///
+/// Once marking is finished, anything the collection didn't mark is garbage. Rather than freeing it
+/// all while holding the `gc_lock`, we walk the tracked data a chunk at a time afterwards. Chunks are
+/// swept by allocations (a chunk each), by the async gc thread in the background, and whatever is
+/// left is swept before the next collection (or when someone synchronizes with the destructors).
+#[derive(Debug)]
+pub(super) struct PendingSweep {
+    /// the collection that did the marking
+    collection: u64,
+    /// how far through the tracked data we are
+    cursor: CLLCursor<GcData>,
+    /// how the collection was spreading out its work (the drops are spread out the same way)
+    parallelism: GcParallelism,
+    /// if set, the garbage is held back until the sweep is done, so it can be destroyed in order
+    ordered: Option<Mutex<Vec<Arc<GcData>>>>,
+    /// how much garbage has been found so far (the pacer wants to know)
+    reclaimed: AtomicUsize,
+}
+
+impl Collector {
+    /// Leave the garbage found by `collection` to be swept lazily
+    pub(super) fn start_sweep(&self, collection: u64, parallelism: GcParallelism) {
+        let mut sweep = self.sweep.lock();
+        debug_assert!(sweep.is_none(), "the last sweep should be done by now");
+
+        *sweep = Some(PendingSweep {
+            collection,
+            cursor: self.tracked_data.data.cursor(),
+            parallelism,
+            ordered: self
+                .ordered_destruction
+                .load(Ordering::SeqCst)
+                .then(Mutex::default),
+            reclaimed: AtomicUsize::new(0),
+        });
+    }
+
+    /// Sweep one chunk, unless there's no sweep to do or someone else is sweeping right now
+    pub(super) fn sweep_step(&self) {
+        if let Some(mut sweep) = self.sweep.try_lock() {
+            self.sweep_chunk_or_finish(&mut sweep);
+        }
+    }
+
+    /// Sweep a chunk at a time until there's nothing left, letting allocations help out in between
+    pub(super) fn sweep_in_background(&self) {
+        loop {
+            let mut sweep = self.sweep.lock();
+            if sweep.is_none() {
+                return;
+            }
+            self.sweep_chunk_or_finish(&mut sweep);
+        }
+    }
+
+    /// Finish the pending sweep (if any), so all the garbage is with the drop thread
+    ///
+    /// The rest of the chunks are shared out across the pool. Each chunk's garbage goes straight to
+    /// the drop thread, so it can get started while we're still sweeping.
+    pub(super) fn finish_sweep(&self) {
+        let mut sweep = self.sweep.lock();
+        let Some(pending) = sweep.as_ref() else {
+            return;
+        };
+
+        if pending.parallelism.is_sequential() {
+            while self.sweep_chunk(pending) {}
+        } else {
+            pending.parallelism.install(|| {
+                rayon::scope(|scope| {
+                    for _ in 0..rayon::current_num_threads() {
+                        scope.spawn(|_| while self.sweep_chunk(pending) {});
+                    }
+                });
+            });
+        }
+
+        self.sweep_finished(&mut sweep);
+    }
+
+    fn sweep_chunk_or_finish(&self, sweep: &mut Option<PendingSweep>) {
+        if let Some(pending) = sweep.as_ref() {
+            if !self.sweep_chunk(pending) {
+                self.sweep_finished(sweep);
+            }
+        }
+    }
+
+    /// Sweep the next chunk of tracked data, sending its garbage to the drop thread. Returns false
+    /// if there were no chunks left
+    fn sweep_chunk(&self, pending: &PendingSweep) -> bool {
+        let collection = pending.collection;
+        let mut garbage = Vec::new();
+
+        let swept_chunk = self
+            .tracked_data
+            .data
+            .retain_chunk(&pending.cursor, |data| {
+                let last_marked = data.last_marked.load(Ordering::SeqCst);
+
+                // Data allocated since marking started is new (and so in use), and anything marked
+                // is still reachable
+                if last_marked == 0 || last_marked == collection {
+                    return true;
+                }
+
+                // Claim it, so reference counting won't free it too. If reference counting got
+                // there first, leave it be (it'll stop tracking the data itself)
+                if data.deallocated.swap(true, Ordering::SeqCst) {
+                    return true;
+                }
+
+                // Save it to be dropped, and stop tracking it
+                garbage.push(data.clone());
+                false
+            });
+
+        pending.reclaimed.fetch_add(garbage.len(), Ordering::SeqCst);
+
+        if let Some(held) = &pending.ordered {
+            held.lock().append(&mut garbage);
+        } else if !garbage.is_empty() {
+            self.send_garbage(DropMessage::DataToDrop(
+                garbage,
+                pending.parallelism.clone(),
+            ));
+        }
+
+        swept_chunk
+    }
+
+    fn sweep_finished(&self, sweep: &mut Option<PendingSweep>) {
+        let Some(pending) = sweep.take() else {
+            return;
+        };
+
+        // update the trigger based on the new baseline
+        self.trigger.record_sweep(
+            self.tracked_data_count(),
+            self.handle_count(),
+            pending.reclaimed.load(Ordering::SeqCst),
+        );
+
+        if let Some(held) = pending.ordered {
+            let garbage = held.into_inner();
+            if !garbage.is_empty() {
+                self.send_garbage(DropMessage::DataToDropInOrder(garbage));
+            }
+        }
+    }
+
+    fn send_garbage(&self, drop_msg: DropMessage) {
+        if let Err(e) = self.dropper.send_msg(drop_msg) {
+            error!("Error sending to drop thread {e}");
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 use parking_lot::Mutex;
 
 // TODO(issue): https://github.com/Others/shredder/issues/8
@@ -5,6 +7,21 @@ const DEFAULT_ALLOCATION_TRIGGER_PERCENT: f32 = 0.75;
 const DEFAULT_HANDLE_DEFICIT_TRIGGER_PERCENT: f32 = 0.9;
 const MIN_ALLOCATIONS_FOR_COLLECTION: f32 = 512.0 * 1.3;
 
+// The pacer never takes the trigger percent outside these bounds
+const MIN_PACED_TRIGGER_PERCENT: f32 = 0.05;
+const MAX_PACED_TRIGGER_PERCENT: f32 = 32.0;
+// ...and never changes it by more than this factor after a single collection
+const MAX_PACER_STEP: f32 = 2.0;
+
+/// A goal for `shredder` to pace collection towards, see `set_gc_pacing_goal`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GcPacingGoal {
+    /// Spend about this fraction of the time collecting (between 0 and 1, exclusive)
+    CpuFraction(f32),
+    /// Have each collection take about this long
+    PauseTime(Duration),
+}
+
 /// Deals with deciding when we need to run a collection
 pub struct GcTrigger {
     data: Mutex<InternalTriggerData>,
@@ -16,11 +33,128 @@ struct InternalTriggerData {
     // Percent less handles than data needed to trigger garbage collection
     handle_deficit_trigger_percent: f32,
     data_count_at_last_collection: usize,
+    handle_count_at_last_collection: usize,
+    // What the pacer is aiming for, if anything
+    pacing_goal: Option<GcPacingGoal>,
+    // The percent the pacer has chosen, used instead of `allocations_trigger_percent` while there's
+    // a goal (so the configured percent comes back when the goal is removed)
+    paced_trigger_percent: Option<f32>,
+    // When the last collection finished
+    last_collection_end: Option<Instant>,
+    // How long the last collection took, and how long since the one before it finished. Kept for the
+    // pacer until the collection's sweep is done, and we know how much it reclaimed
+    unpaced_collection: Option<(Duration, Option<Duration>)>,
+}
+
+impl InternalTriggerData {
+    fn trigger_percent(&self) -> f32 {
+        self.paced_trigger_percent
+            .unwrap_or(self.allocations_trigger_percent)
+    }
+
+    /// Adjust the paced trigger percent after a collection that took `duration` (`since_last`
+    /// after the previous one finished), and reclaimed `reclaimed` out of `total` pieces of data
+    ///
+    /// The time until the next collection is roughly proportional to the trigger percent, and so is
+    /// the amount of garbage it will find. So when aiming for a fraction of the CPU, we scale the
+    /// percent by how much longer (or shorter) we'd like to go between collections. When aiming
+    /// for a pause time, we scale it by how much longer (or shorter) we'd like collections to be.
+    ///
+    /// Collecting more often only helps if collections find garbage though. So the pacer only moves
+    /// that way in proportion to the share of the data the collection reclaimed: if it found
+    /// nothing, collecting sooner would just mean doing the same work again.
+    fn pace(
+        &mut self,
+        duration: Duration,
+        since_last: Option<Duration>,
+        reclaimed: usize,
+        total: usize,
+    ) {
+        let adjustment = match self.pacing_goal {
+            Some(GcPacingGoal::CpuFraction(fraction)) => {
+                let Some(since_last) = since_last else {
+                    return;
+                };
+                let time_between = since_last.saturating_sub(duration);
+                let wanted_time_between = duration.as_secs_f32() * (1.0 - fraction) / fraction;
+                wanted_time_between / time_between.as_secs_f32()
+            }
+            Some(GcPacingGoal::PauseTime(budget)) => budget.as_secs_f32() / duration.as_secs_f32(),
+            None => return,
+        };
+
+        // (Zero times give us NaN or infinity, which tell us nothing)
+        if !adjustment.is_finite() || adjustment == 0.0 {
+            return;
+        }
+
+        let mut adjustment = adjustment.clamp(1.0 / MAX_PACER_STEP, MAX_PACER_STEP);
+        if adjustment < 1.0 {
+            let reclaimed_share = if total == 0 {
+                0.0
+            } else {
+                reclaimed as f32 / total as f32
+            };
+            adjustment = 1.0 - (1.0 - adjustment) * reclaimed_share;
+        }
+
+        let percent = self.trigger_percent() * adjustment;
+        self.paced_trigger_percent =
+            Some(percent.clamp(MIN_PACED_TRIGGER_PERCENT, MAX_PACED_TRIGGER_PERCENT));
+    }
 }
 
 impl GcTrigger {
+    /// Set the configured trigger percent. (This is also the pacer's new starting point)
     pub fn set_trigger_percent(&self, p: f32) {
-        self.data.lock().allocations_trigger_percent = p;
+        let mut internal_data = self.data.lock();
+        internal_data.allocations_trigger_percent = p;
+        if internal_data.paced_trigger_percent.is_some() {
+            internal_data.paced_trigger_percent = Some(p);
+        }
+    }
+
+    /// The trigger percent in use (the pacer's, if there's a pacing goal)
+    pub fn trigger_percent(&self) -> f32 {
+        self.data.lock().trigger_percent()
+    }
+
+    pub fn set_pacing_goal(&self, goal: Option<GcPacingGoal>) {
+        let mut internal_data = self.data.lock();
+        internal_data.pacing_goal = goal;
+        if goal.is_none() {
+            internal_data.paced_trigger_percent = None;
+            internal_data.unpaced_collection = None;
+        } else if internal_data.paced_trigger_percent.is_none() {
+            internal_data.paced_trigger_percent = Some(internal_data.allocations_trigger_percent);
+        }
+    }
+
+    /// Note that a collection just took `duration`. Once its sweep is done (see `record_sweep`)
+    /// the pacer adjusts the trigger percent
+    pub fn record_collection(&self, duration: Duration) {
+        let now = Instant::now();
+        let mut internal_data = self.data.lock();
+        let since_last = internal_data
+            .last_collection_end
+            .replace(now)
+            .map(|last_end| now - last_end);
+
+        if internal_data.pacing_goal.is_some() {
+            internal_data.unpaced_collection = Some((duration, since_last));
+        }
+    }
+
+    /// Note that the last collection's sweep is done, reclaiming `reclaimed` pieces of data and
+    /// leaving `data_count` (with `handle_count` handles). This is the new baseline for the trigger
+    pub fn record_sweep(&self, data_count: usize, handle_count: usize, reclaimed: usize) {
+        let mut internal_data = self.data.lock();
+        internal_data.data_count_at_last_collection = data_count;
+        internal_data.handle_count_at_last_collection = handle_count;
+
+        if let Some((duration, since_last)) = internal_data.unpaced_collection.take() {
+            internal_data.pace(duration, since_last, reclaimed, data_count + reclaimed);
+        }
     }
 
     pub fn should_collect(&self, current_data_count: usize, current_handle_count: usize) -> bool {
@@ -38,7 +172,9 @@ impl GcTrigger {
             return true;
         }
 
-        let amount_of_new_data = current_data_count - internal_data.data_count_at_last_collection;
+        // (Data can be freed between collections, so there may be less than last time)
+        let amount_of_new_data =
+            current_data_count.saturating_sub(internal_data.data_count_at_last_collection);
         let percent_more_data =
             amount_of_new_data as f32 / internal_data.data_count_at_last_collection as f32;
 
@@ -47,13 +183,20 @@ impl GcTrigger {
             return true;
         }
 
-        // Otherwise base our decision off the configured gc_trigger_percent
-        percent_more_data >= internal_data.allocations_trigger_percent
+        // Otherwise base our decision off the gc_trigger_percent (configured, or chosen by the pacer)
+        percent_more_data >= internal_data.trigger_percent()
     }
 
-    pub fn set_data_count_after_collection(&self, data_count: usize) {
-        let mut internal_data = self.data.lock();
-        internal_data.data_count_at_last_collection = data_count;
+    /// Has anything been allocated or dropped since the last collection? (If not, collecting again
+    /// won't find anything new)
+    pub fn changed_since_collection(&self, data_count: usize, handle_count: usize) -> bool {
+        let internal_data = self.data.lock();
+        data_count != internal_data.data_count_at_last_collection
+            || handle_count != internal_data.handle_count_at_last_collection
+    }
+
+    pub fn last_collection_end(&self) -> Option<Instant> {
+        self.data.lock().last_collection_end
     }
 }
 
@@ -64,6 +207,11 @@ impl Default for GcTrigger {
                 allocations_trigger_percent: DEFAULT_ALLOCATION_TRIGGER_PERCENT,
                 handle_deficit_trigger_percent: DEFAULT_HANDLE_DEFICIT_TRIGGER_PERCENT,
                 data_count_at_last_collection: 0,
+                handle_count_at_last_collection: 0,
+                pacing_goal: None,
+                paced_trigger_percent: None,
+                last_collection_end: None,
+                unpaced_collection: None,
             }),
         }
     }
@@ -32,7 +32,7 @@ unsafe impl<T> Send for Chunk<T> where T: Send + Sync {}
 unsafe impl<T> Sync for Chunk<T> where T: Sync {}
 
 impl<T> Chunk<T> {
-    fn iter_this<F: Fn(Arc<T>) + Sync>(&self, f: &F) {
+    fn iter_this<F: Fn(Arc<T>)>(&self, f: &F) {
         for i in 0..CHUNK_SIZE {
             let v = Guard::into_inner(self.values[i].load());
             if let Some(arc) = v {
@@ -41,6 +41,17 @@ impl<T> Chunk<T> {
         }
     }
 
+    fn iter_rest<F: Fn(Arc<T>)>(&self, f: &F) {
+        let mut chunk = self;
+        loop {
+            chunk.iter_this(f);
+            if chunk.next.is_null() {
+                return;
+            }
+            chunk = unsafe { &*chunk.next };
+        }
+    }
+
     fn par_iter_rest<F: Fn(Arc<T>) + Sync>(&self, f: &F)
     where
         T: Send + Sync,
@@ -59,10 +70,7 @@ impl<T> Chunk<T> {
         }
     }
 
-    fn retain_this<F: Fn(&Arc<T>) -> bool + Sync>(&self, f: &F, host: &ChunkedLinkedList<T>)
-    where
-        T: Send + Sync,
-    {
+    fn retain_this<F: FnMut(&Arc<T>) -> bool>(&self, mut f: F, host: &ChunkedLinkedList<T>) {
         for i in 0..CHUNK_SIZE {
             let current = self.values[i].load();
             let should_retain = match &*current {
@@ -81,22 +89,6 @@ impl<T> Chunk<T> {
             }
         }
     }
-
-    fn par_retain_rest<F: Fn(&Arc<T>) -> bool + Sync>(&self, f: &F, host: &ChunkedLinkedList<T>)
-    where
-        T: Send + Sync,
-    {
-        if self.next.is_null() {
-            self.retain_this(f, host);
-        } else {
-            let next = unsafe { &*self.next };
-
-            rayon::join(
-                || self.retain_this(f, host),
-                || next.par_retain_rest(f, host),
-            );
-        }
-    }
 }
 
 #[derive(Debug)]
@@ -119,6 +111,43 @@ impl<T> Clone for CLLItem<T> {
     }
 }
 
+impl<T> CLLItem<T> {
+    /// Where this item lives, without holding onto the item itself
+    pub fn slot(&self) -> CLLSlot<T> {
+        CLLSlot {
+            from: self.from,
+            idx: self.idx,
+        }
+    }
+}
+
+/// The position of an item in a `ChunkedLinkedList`. (Can be stored inside the item, unlike a
+/// `CLLItem`, without making a reference cycle)
+#[derive(Debug)]
+pub struct CLLSlot<T> {
+    from: *const Chunk<T>,
+    idx: usize,
+}
+
+unsafe impl<T> Send for CLLSlot<T> where T: Send + Sync {}
+unsafe impl<T> Sync for CLLSlot<T> where T: Sync {}
+
+impl<T> Clone for CLLSlot<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for CLLSlot<T> {}
+
+/// Where an incremental walk over a `ChunkedLinkedList` is up to (see `retain_chunk`)
+///
+/// Many threads can share a cursor, in which case each chunk goes to just one of them.
+#[derive(Debug)]
+pub struct CLLCursor<T> {
+    next: AtomicPtr<Chunk<T>>,
+}
+
 impl<T> ChunkedLinkedList<T> {
     pub fn new() -> Self {
         let free_entries = SegQueue::new();
@@ -197,28 +226,68 @@ impl<T> ChunkedLinkedList<T> {
         }
     }
 
-    pub fn remove(&self, cll_item: &CLLItem<T>) {
-        let chunk = unsafe { &*cll_item.from };
+    /// Remove an item, returning whether it was still in the list (so it's safe to call twice)
+    pub fn remove(&self, cll_item: &CLLItem<T>) -> bool {
+        self.remove_from_slot(cll_item.slot(), &cll_item.v)
+    }
 
-        let slot = &chunk.values[cll_item.idx];
-        let res = slot.compare_and_swap(&cll_item.v, None);
+    /// Remove `v` from `slot`, returning whether it was still there
+    pub fn remove_from_slot(&self, slot: CLLSlot<T>, v: &Arc<T>) -> bool {
+        let chunk = unsafe { &*slot.from };
+
+        let value = &chunk.values[slot.idx];
+        let res = value.compare_and_swap(v, None);
 
         if let Some(prev_v) = &*res {
-            if Arc::as_ptr(prev_v) == Arc::as_ptr(&cll_item.v) {
+            if Arc::as_ptr(prev_v) == Arc::as_ptr(v) {
                 // We did a remove, so record that swap
                 self.estimated_len.fetch_sub(1, Ordering::Relaxed);
 
-                self.free_entries.push((cll_item.from, cll_item.idx))
+                self.free_entries.push((slot.from, slot.idx));
+                return true;
             }
         }
+
+        false
     }
 
-    pub fn par_retain<F: Fn(&Arc<T>) -> bool + Sync>(&self, f: F)
-    where
-        T: Send + Sync,
-    {
+    pub fn for_each<F: Fn(Arc<T>)>(&self, f: F) {
         let head = unsafe { &*self.head.load(Ordering::Relaxed) };
-        head.par_retain_rest(&f, self);
+        head.iter_rest(&f);
+    }
+
+    /// Start walking the list, a chunk at a time. Items in chunks added after this are skipped
+    pub fn cursor(&self) -> CLLCursor<T> {
+        CLLCursor {
+            next: AtomicPtr::new(self.head.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Run `retain` on the next chunk of `cursor`. Returns false if there were no chunks left
+    pub fn retain_chunk<F: FnMut(&Arc<T>) -> bool>(&self, cursor: &CLLCursor<T>, f: F) -> bool {
+        let mut next = cursor.next.load(Ordering::SeqCst);
+        loop {
+            if next.is_null() {
+                return false;
+            }
+
+            // Chunks are never deallocated, so this is safe
+            let chunk = unsafe { &*next };
+
+            // Claim the chunk, so no-one else sharing this cursor gets it too
+            match cursor.next.compare_exchange(
+                next,
+                chunk.next.cast_mut(),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => {
+                    chunk.retain_this(f, self);
+                    return true;
+                }
+                Err(actual) => next = actual,
+            }
+        }
     }
 
     pub fn par_iter<F: Fn(Arc<T>) + Sync>(&self, f: F)
@@ -237,3 +306,43 @@ impl<T> ChunkedLinkedList<T> {
 fn initialize_values<T>() -> [ArcSwapOption<T>; CHUNK_SIZE] {
     [(); CHUNK_SIZE].map(|_| ArcSwapOption::new(None))
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{ChunkedLinkedList, CHUNK_SIZE};
+
+    #[test]
+    fn retain_chunk_walks_every_chunk_once() {
+        let list = ChunkedLinkedList::new();
+        for i in 0..=(CHUNK_SIZE * 2) {
+            list.insert(Arc::new(i));
+        }
+
+        let cursor = list.cursor();
+        let mut chunks = 0;
+        while list.retain_chunk(&cursor, |v| **v % 2 == 0) {
+            chunks += 1;
+        }
+        assert_eq!(chunks, 3);
+        assert!(!list.retain_chunk(&cursor, |_| false));
+        assert_eq!(list.estimate_len(), CHUNK_SIZE + 1);
+    }
+
+    #[test]
+    fn retain_chunk_skips_chunks_added_later() {
+        let list = ChunkedLinkedList::new();
+        list.insert(Arc::new(0));
+        let cursor = list.cursor();
+
+        // Fill up the first chunk, so a new one is added
+        for i in 1..=CHUNK_SIZE {
+            list.insert(Arc::new(i));
+        }
+
+        assert!(list.retain_chunk(&cursor, |_| false));
+        assert!(!list.retain_chunk(&cursor, |_| false));
+        assert_eq!(list.estimate_len(), 1);
+    }
+}
@@ -1,5 +1,6 @@
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::thread::yield_now;
 
 use parking_lot::Condvar;
 use parking_lot::Mutex;
@@ -83,6 +84,33 @@ impl Lockout {
             None
         }
     }
+
+    /// Like `get_exclusive_warrant`, but waits for everyone else's warrants to be released
+    ///
+    /// Releasing a (non-exclusive) warrant doesn't wake anyone, so this spins while those are held.
+    /// Only use it when nobody else should be holding a warrant for long.
+    pub fn wait_for_exclusive_warrant<P: LockoutProvider>(provider: P) -> ExclusiveWarrant<P> {
+        loop {
+            let swap_result = provider.provide().count.compare_exchange(
+                0,
+                EXCLUSIVE_SIGNPOST,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            );
+            if swap_result.is_ok() {
+                return ExclusiveWarrant { provider };
+            }
+
+            let lockout = provider.provide();
+            let mut guard = lockout.lockout_mutex.lock();
+            if lockout.count.load(Ordering::SeqCst) == EXCLUSIVE_SIGNPOST {
+                lockout.lockout_condvar.wait(&mut guard);
+            } else {
+                drop(guard);
+                yield_now();
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
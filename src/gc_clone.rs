@@ -0,0 +1,124 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::collector::GcData;
+use crate::marker::GcDrop;
+use crate::{Gc, Scan};
+
+/// A trait for data that can be deeply cloned, along with every `Gc` it can reach.
+///
+/// This is what powers `deep_clone`. Implementations should clone each field using that field's
+/// `gc_clone` method, passing along the `GcCloner`. The `GcCloner` remembers every `Gc` it has
+/// already copied, which is how sharing and cycles are preserved in the copy.
+///
+/// Data that can't contain a `Gc` can just be cloned normally.
+///
+/// There is no derive for `GcClone` yet (it needs support in `shredder_derive`), so for now
+/// implementations are written by hand.
+///
+/// # Example
+/// ```
+/// use std::cell::RefCell;
+///
+/// use shredder::{deep_clone, Gc, GcClone, GcCloner, Scan};
+///
+/// #[derive(Scan)]
+/// struct Node {
+///     value: u32,
+///     next: Option<Gc<RefCell<Node>>>,
+/// }
+///
+/// impl GcClone for Node {
+///     fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+///         Self {
+///             value: self.value.gc_clone(cloner),
+///             next: self.next.gc_clone(cloner),
+///         }
+///     }
+/// }
+///
+/// let a = Gc::new(RefCell::new(Node { value: 1, next: None }));
+/// a.borrow_mut().next = Some(a.clone());
+///
+/// let copy = deep_clone(&a);
+/// assert!(!copy.ptr_eq(&a));
+/// // The copy points to itself, just like the original did
+/// assert!(copy.borrow().next.as_ref().unwrap().ptr_eq(&copy));
+/// ```
+pub trait GcClone: Scan + Sized {
+    /// Clone this data, using `cloner` to copy any `Gc`s inside it
+    #[must_use]
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self;
+}
+
+/// Tracks the copies made during a `deep_clone`, so each piece of `Gc` data is copied only once
+///
+/// Usually you will only care about this while implementing `GcClone`
+#[derive(Default)]
+pub struct GcCloner {
+    /// holding onto the `Arc`s keeps the pointers we use as keys from being reused
+    originals: Vec<Arc<GcData>>,
+    /// maps the original data to a type-erased `Gc<T>` pointing at its copy
+    copies: HashMap<*const GcData, Box<dyn Any>>,
+}
+
+impl GcCloner {
+    /// Create a new `GcCloner`, which hasn't copied anything yet
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a copy of the data behind `gc`, copying it if this cloner hasn't seen it before
+    ///
+    /// The copy is allocated with `Gc::new_cyclic`, so its destructor will be run when it is
+    /// collected. While the copy is being built, the `Gc` to it can be cloned and stored, but not
+    /// accessed.
+    ///
+    /// # Panics
+    /// Panics if the data behind `gc` is being mutably borrowed or locked in a way that stops it
+    /// from being read (for example a `RefCell` that is already mutably borrowed).
+    pub fn clone_gc<T>(&mut self, gc: &Gc<T>) -> Gc<T>
+    where
+        T: GcClone + GcDrop + 'static,
+    {
+        let original_data = gc.internal_handle_ref().data();
+        let key = Arc::as_ptr(original_data);
+
+        if let Some(copy) = self.copies.get(&key) {
+            return copy
+                .downcast_ref::<Gc<T>>()
+                .expect("the same data was deep cloned as two different types")
+                .clone();
+        }
+
+        Gc::new_cyclic(|copy| {
+            // Record the copy before recursing, so cycles back to this data find it
+            self.originals.push(original_data.clone());
+            self.copies.insert(key, Box::new(copy));
+
+            let original = gc.get();
+            original.gc_clone(self)
+        })
+    }
+}
+
+/// Copy all the data reachable from `gc`, returning a `Gc` to the copy of `gc`'s data
+///
+/// The shape of the graph is preserved: if two `Gc`s point to the same data in the original, the
+/// corresponding `Gc`s in the copy will point to the same (copied) data. Cycles are reproduced
+/// too. See `GcClone` for how to make your own types deep cloneable.
+///
+/// This recurses once for each `Gc` on the way to the furthest data, so extremely deep structures
+/// (like a million element linked list) may overflow the stack.
+///
+/// # Panics
+/// Panics under the same conditions as `GcCloner::clone_gc`.
+#[must_use]
+pub fn deep_clone<T>(gc: &Gc<T>) -> Gc<T>
+where
+    T: GcClone + GcDrop + 'static,
+{
+    GcCloner::new().clone_gc(gc)
+}
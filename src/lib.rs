@@ -57,29 +57,45 @@ extern crate rental;
 
 /// Atomic gc operations
 pub mod atomic;
+mod cell;
+/// Concurrent and persistent data structures built on `Gc`
+pub mod collections;
 mod collector;
 mod concurrency;
 mod finalize;
+mod gc_clone;
 /// Marker types
 pub mod marker;
 /// Various types used for plumbing, stuff you don't need to care about
 pub mod plumbing;
 mod r;
 mod scan;
+/// Serializing `Gc` graphs with serde (requires the `serde` feature)
+#[cfg(feature = "serde")]
+pub mod serde_graph;
 mod smart_ptr;
 mod std_impls;
+/// Walking the object graph behind a `Gc`
+pub mod visit;
 /// Helpful wrappers used for convenience methods
 pub mod wrappers;
 
+use std::alloc::GlobalAlloc;
 use std::cell::RefCell;
 use std::sync::{Mutex, RwLock};
 
 use crate::collector::COLLECTOR;
 
+pub use crate::cell::{GcCell, GcCellRef, GcCellRefMut};
+pub use crate::collector::{
+    AllocError, GcPacingGoal, GcParallelism, GcPauseGuard, GcSchedule, PendingDestructionLimit,
+    SetAllocatorError,
+};
 pub use crate::finalize::{Finalize, FinalizeFields};
+pub use crate::gc_clone::{deep_clone, GcClone, GcCloner};
 pub use crate::r::{RMut, R};
 pub use crate::scan::{Scan, Scanner, ToScan};
-pub use crate::smart_ptr::{DerefGc, Gc, GcGuard};
+pub use crate::smart_ptr::{DerefGc, Gc, GcGuard, GcGuardMut, GcOpaque};
 
 /// A convenient alias for `Gc<RefCell<T>>`.
 /// Note that `Gc<RefCell<T>>` has additional specialized methods for working with `RefCell`s inside
@@ -124,6 +140,59 @@ pub fn number_of_active_handles() -> usize {
     COLLECTOR.handle_count()
 }
 
+/// Returns how many pieces of garbage are waiting for the background thread to run their
+/// destructors.
+///
+/// Garbage is only counted once it's been swept up after a collection (see `synchronize_destructors`
+/// to wait for it to be destroyed).
+///
+/// # Example
+/// ```
+/// use shredder::{collect, number_of_pending_destructions, synchronize_destructors, Gc};
+///
+/// drop(Gc::new(128));
+/// collect();
+/// synchronize_destructors();
+/// assert_eq!(number_of_pending_destructions(), 0);
+/// ```
+#[must_use]
+pub fn number_of_pending_destructions() -> usize {
+    COLLECTOR.pending_destruction_count()
+}
+
+/// Returns how many bytes of garbage are waiting for the background thread to run their
+/// destructors.
+///
+/// This only counts the data directly inside each `Gc`, not anything it owns (like the contents of
+/// a `Vec`).
+#[must_use]
+pub fn pending_destruction_bytes() -> usize {
+    COLLECTOR.pending_destruction_bytes()
+}
+
+/// Limits how much garbage can be waiting for its destructors. There are no limits by default.
+///
+/// Garbage is destroyed on a background thread. If its destructors are slow (closing sockets, for
+/// instance) garbage can pile up faster than it's destroyed. With a limit set, allocating a `Gc`
+/// blocks while more garbage than the limit is waiting, until the background thread catches up.
+/// See `number_of_pending_destructions` and `pending_destruction_bytes` for what is counted.
+///
+/// Allocations made by destructors never block, since the background thread may be waiting for
+/// them. But be careful not to allocate while holding a lock a destructor needs.
+///
+/// # Example
+/// ```
+/// use shredder::{set_gc_pending_destruction_limit, PendingDestructionLimit};
+///
+/// set_gc_pending_destruction_limit(PendingDestructionLimit {
+///     objects: Some(100_000),
+///     bytes: Some(64 * 1024 * 1024),
+/// });
+/// ```
+pub fn set_gc_pending_destruction_limit(limit: PendingDestructionLimit) {
+    COLLECTOR.set_pending_destruction_limit(limit);
+}
+
 /// Sets the percent more data that'll trigger collection.
 ///
 /// `shredder`'s collection automatically triggers when:
@@ -151,6 +220,71 @@ pub fn set_gc_trigger_percent(percent: f32) {
     COLLECTOR.set_gc_trigger_percent(percent)
 }
 
+/// Returns the current `gc_trigger_percent` (see `set_gc_trigger_percent`).
+///
+/// This is mostly useful to see what the pacer has chosen (see `set_gc_pacing_goal`).
+///
+/// # Example
+/// ```
+/// use shredder::{gc_trigger_percent, set_gc_trigger_percent};
+///
+/// set_gc_trigger_percent(1.5);
+/// assert_eq!(gc_trigger_percent(), 1.5);
+/// ```
+#[must_use]
+pub fn gc_trigger_percent() -> f32 {
+    COLLECTOR.gc_trigger_percent()
+}
+
+/// Sets a goal for `shredder` to pace automatic collection towards, or `None` to stop pacing.
+/// There's no goal by default.
+///
+/// The right `gc_trigger_percent` depends on how fast your program allocates, and how much of what
+/// it allocates survives, so it's hard to choose by hand. With a goal set, `shredder` times each
+/// collection, and after each one adjusts the trigger percent to bring the next closer to the goal:
+/// - `GcPacingGoal::CpuFraction` aims to spend that fraction of the time collecting. If
+///   collections take up more than that, they're spaced out further.
+/// - `GcPacingGoal::PauseTime` aims for each collection to take that long. Collecting more often
+///   means each collection has less garbage to deal with, and so is quicker.
+///
+/// How much each collection reclaims is accounted for too. Collecting more often only helps if
+/// collections find garbage, so the pacer only moves that way as far as the share of data the last
+/// collection reclaimed. (A collection that found nothing never makes the next one come sooner.)
+/// The pacer adjusts the percent gradually, and keeps it between 0.05 and 32. Setting the percent
+/// yourself gives the pacer a new starting point. When the goal is removed, the percent goes back
+/// to the one you set (or the default).
+///
+/// Note that most of a collection runs alongside your program. Only starting and finishing
+/// marking holds up atomic operations, and allocation never waits for a collection.
+///
+/// # Panics
+/// This function will panic if the CPU fraction isn't between 0 and 1 (exclusive), or the pause
+/// time is zero.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use shredder::{set_gc_pacing_goal, GcPacingGoal};
+///
+/// set_gc_pacing_goal(Some(GcPacingGoal::CpuFraction(0.05))); // Collect ~5% of the time
+/// set_gc_pacing_goal(Some(GcPacingGoal::PauseTime(Duration::from_millis(2))));
+/// set_gc_pacing_goal(None); // Stop pacing
+/// ```
+pub fn set_gc_pacing_goal(goal: Option<GcPacingGoal>) {
+    match goal {
+        Some(GcPacingGoal::CpuFraction(fraction)) => assert!(
+            fraction > 0.0 && fraction < 1.0,
+            "The CPU fraction must be between 0 and 1! (fraction = {})",
+            fraction
+        ),
+        Some(GcPacingGoal::PauseTime(pause)) => {
+            assert!(!pause.is_zero(), "The pause time cannot be zero!");
+        }
+        None => {}
+    }
+    COLLECTOR.set_pacing_goal(goal);
+}
+
 /// A function for manually running a collection, ignoring the heuristic that governs normal
 /// garbage collector operations.
 ///
@@ -168,6 +302,79 @@ pub fn collect() {
     COLLECTOR.collect();
 }
 
+/// Stops `shredder` from starting collections automatically, until the returned guard is dropped.
+///
+/// A collection briefly stops atomic operations while it starts and finishes marking, and takes
+/// up CPU time. This lets latency-critical sections (like audio callbacks) keep it out of the way.
+/// Guards can be nested, or held on several threads, and automatic collection resumes when the
+/// last one is dropped. If a collection was put off in the meantime, it's started then.
+///
+/// Calling `collect` still collects, and a collection that has already started isn't stopped.
+///
+/// # Example
+/// ```
+/// use shredder::{pause_collection, Gc};
+///
+/// let guard = pause_collection();
+/// // No automatic collections start in here
+/// let data = Gc::new(128);
+/// drop(guard); // Now one can start, if it's needed
+/// ```
+pub fn pause_collection() -> GcPauseGuard {
+    COLLECTOR.pause_collection()
+}
+
+/// Set when `shredder` should collect, besides when allocation triggers a collection.
+///
+/// Normally collections only start when your program allocates, so garbage made just before a
+/// quiet period sits around until the next burst of allocation. A schedule lets the background
+/// collection thread start collections on its own:
+/// - `GcSchedule::interval` collects at least that often.
+/// - `GcSchedule::idle_after` collects once nothing has been allocated for that long.
+///
+/// Scheduled collections are skipped if nothing has been allocated or dropped since the last
+/// collection, or while collection is paused (see `pause_collection`). The default schedule is
+/// empty.
+///
+/// # Panics
+/// This function will panic if either duration is zero.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use shredder::{set_gc_schedule, GcSchedule};
+///
+/// set_gc_schedule(GcSchedule {
+///     interval: Some(Duration::from_secs(10)),
+///     idle_after: Some(Duration::from_millis(500)),
+/// });
+/// set_gc_schedule(GcSchedule::default()); // Back to collecting only on allocation
+/// ```
+pub fn set_gc_schedule(schedule: GcSchedule) {
+    for duration in [schedule.interval, schedule.idle_after].iter().flatten() {
+        assert!(!duration.is_zero(), "Scheduled durations cannot be zero!");
+    }
+    COLLECTOR.set_schedule(schedule);
+}
+
+/// Tell `shredder` that your program is idle, so now is a good time to collect.
+///
+/// This is for embedders that know when they're idle (like an event loop with nothing to do, or a
+/// game between frames). The background collection thread starts a collection soon after, unless
+/// nothing has changed since the last collection or collection is paused. It doesn't block.
+///
+/// # Example
+/// ```
+/// use shredder::{report_idle, Gc};
+///
+/// let data = Gc::new(128);
+/// drop(data);
+/// report_idle(); // The garbage can be collected while we wait for more work
+/// ```
+pub fn report_idle() {
+    COLLECTOR.report_idle();
+}
+
 /// Block the current thread until the background thread has finished running the destructors for
 /// all data that was marked as garbage at the point this method was called.
 ///
@@ -188,6 +395,176 @@ pub fn synchronize_destructors() {
     COLLECTOR.synchronize_destructors()
 }
 
+/// Run the destructors of any garbage allocated by this thread with `Gc::new_local`.
+///
+/// That garbage is sent back to this thread rather than destroyed in the background, and waits
+/// here until this is called (or until this thread next allocates a `Gc`). Calling this from inside
+/// a destructor does nothing.
+///
+/// # Example
+/// ```
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+/// use shredder::{collect, run_local_destructors, synchronize_destructors, Gc, Scan};
+///
+/// #[derive(Scan)]
+/// #[shredder(cant_drop)]
+/// struct Counted {
+///     #[shredder(unsafe_skip_gc_safe)]
+///     count: Rc<Cell<u32>>,
+/// }
+///
+/// impl Drop for Counted {
+///     fn drop(&mut self) {
+///         self.count.set(self.count.get() + 1);
+///     }
+/// }
+///
+/// let count = Rc::new(Cell::new(0));
+/// drop(Gc::new_local(Counted { count: count.clone() }));
+///
+/// collect();
+/// synchronize_destructors(); // Now the garbage is waiting on this thread
+/// assert_eq!(count.get(), 0);
+///
+/// run_local_destructors();
+/// assert_eq!(count.get(), 1);
+/// ```
+pub fn run_local_destructors() {
+    COLLECTOR.run_local_destructors();
+}
+
+/// Sets how `shredder` spreads out collection (and destructor) work across threads.
+///
+/// By default `shredder` uses rayon's global thread pool, which means it competes with whatever
+/// else your program runs in that pool. See `GcParallelism` for the alternatives. A change takes
+/// effect from the next collection.
+///
+/// # Example
+/// ```
+/// use shredder::{collect, set_gc_parallelism, GcParallelism};
+///
+/// set_gc_parallelism(GcParallelism::private_pool(2).unwrap());
+/// collect(); // Runs in the new pool
+///
+/// set_gc_parallelism(GcParallelism::Sequential);
+/// collect(); // Runs entirely on this thread
+/// ```
+pub fn set_gc_parallelism(parallelism: GcParallelism) {
+    COLLECTOR.set_parallelism(parallelism);
+}
+
+/// Turns reference counting on (or off). It's off by default.
+///
+/// With reference counting on, data is freed (and its destructor run) as soon as the last `Gc`
+/// pointing to it is dropped, on the thread that dropped it. Collection is then only needed for
+/// cycles. This gives deterministic cleanup for data that's never part of a cycle, at the cost of
+/// some extra work whenever a `Gc` is dropped.
+///
+/// There are a few exceptions, which are still left for collection:
+/// - data that has ever been stored in an `AtomicGc` (or `AtomicOptionGc`), since atomics aren't
+///   counted
+/// - data whose last `Gc` is dropped by a destructor while a collection is running (the collection
+///   frees it before it's done)
+///
+/// If a collection is running when the last `Gc` is dropped, the drop waits for it to finish.
+///
+/// # Example
+/// ```
+/// use shredder::{set_gc_reference_counting, Gc};
+///
+/// set_gc_reference_counting(true);
+///
+/// let data = Gc::new(String::from("freed right away"));
+/// drop(data); // The `String` is dropped here
+/// ```
+pub fn set_gc_reference_counting(enabled: bool) {
+    COLLECTOR.set_reference_counting(enabled);
+}
+
+/// Turns ordered destruction on (or off). It's off by default.
+///
+/// Normally the garbage a collection finds is destroyed all at once, in no particular order (and
+/// possibly in parallel). So a destructor can't rely on the data its `Gc`s point to, which may
+/// already be gone. Accessing it panics.
+///
+/// With ordered destruction on, garbage is destroyed one piece at a time, with data destroyed
+/// before the data it points to. A destructor (or finalizer) can then access the data its `Gc`s
+/// point to, for example to flush something to a child resource. A cycle has no such order: its
+/// members are destroyed one after the other, in no particular order, so within a cycle the
+/// destructors can't rely on each other. They can still rely on anything the cycle points to
+/// outside of itself, since that's destroyed after the whole cycle.
+///
+/// This costs a graph walk over the garbage, and the garbage from a collection is held back until
+/// it's all been found. Only garbage found from the next collection on is ordered. Data freed by
+/// reference counting is already freed in this order, while data made with `Gc::new_local` is
+/// destroyed on its own thread, and isn't ordered with the rest.
+///
+/// # Example
+/// ```
+/// use std::sync::Mutex;
+/// use shredder::{collect, set_gc_ordered_destruction, synchronize_destructors, Gc, Scan};
+///
+/// #[derive(Scan)]
+/// struct Log {
+///     lines: Mutex<Vec<String>>,
+/// }
+///
+/// #[derive(Scan)]
+/// struct Writer {
+///     log: Gc<Log>,
+/// }
+///
+/// impl Drop for Writer {
+///     fn drop(&mut self) {
+///         // The log is garbage too, but it's destroyed after this writer
+///         self.log.get().lines.lock().unwrap().push(String::from("flushed"));
+///     }
+/// }
+///
+/// set_gc_ordered_destruction(true);
+///
+/// drop(Gc::new(Writer {
+///     log: Gc::new(Log { lines: Mutex::new(Vec::new()) }),
+/// }));
+/// collect();
+/// synchronize_destructors();
+/// ```
+pub fn set_gc_ordered_destruction(enabled: bool) {
+    COLLECTOR.set_ordered_destruction(enabled);
+}
+
+/// Sets the allocator that data in `Gc`s (and `DerefGc`s) is allocated with.
+///
+/// By default `Gc` data comes from the `#[global_allocator]`, like everything else. Setting a
+/// separate allocator lets you attribute, and bound, garbage collected memory separately from the
+/// rest of your program. (If the allocator refuses an allocation, `Gc::try_new` reports it.)
+///
+/// This has to be called before any `Gc` data is allocated, since data must be given back to the
+/// allocator it came from. Only the data itself uses this allocator: the collector's bookkeeping,
+/// and boxes given to `Gc::from_box`, use the global allocator.
+///
+/// # Errors
+/// Returns `SetAllocatorError` if `Gc` data has already been allocated (or the allocator was
+/// already set).
+///
+/// # Example
+/// ```
+/// use std::alloc::System;
+/// use shredder::{set_gc_allocator, Gc};
+///
+/// set_gc_allocator(&System).unwrap();
+///
+/// let data = Gc::new(128);
+/// assert_eq!(*data.get(), 128);
+/// assert!(set_gc_allocator(&System).is_err());
+/// ```
+pub fn set_gc_allocator(
+    allocator: &'static (dyn GlobalAlloc + Sync),
+) -> Result<(), SetAllocatorError> {
+    collector::set_allocator(allocator)
+}
+
 /// A convenience method for helping ensure your destructors are run.
 ///
 /// In Rust you can never assume that destructors run, but using this method helps `shredder` not
@@ -206,6 +583,7 @@ pub fn run_with_gc_cleanup<T, F: FnOnce() -> T>(f: F) -> T {
 
     collect();
     synchronize_destructors();
+    run_local_destructors();
 
     res
 }
@@ -4,7 +4,7 @@ use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
 use crate::marker::{GcDeref, GcDrop, GcSafe};
-use crate::{Finalize, Scan, Scanner};
+use crate::{Finalize, GcClone, GcCloner, Scan, Scanner};
 
 // Only straight up `'static` references can be `Scan` or `GcSafe`, since other references may
 // become invalid after their lifetime ends
@@ -27,6 +27,17 @@ unsafe impl<T> Finalize for &'static T {
     unsafe fn finalize(&mut self) {}
 }
 
+// A `'static` reference can't point into a `Gc`, so a copy of the reference is a deep copy
+impl<T> GcClone for &'static T
+where
+    &'static T: Send,
+{
+    #[inline]
+    fn gc_clone(&self, _: &mut GcCloner) -> Self {
+        Clone::clone(self)
+    }
+}
+
 // But other references can become safe through careful manipulation!
 
 /// A `GcSafe` version of `&T`
@@ -97,6 +108,14 @@ unsafe impl<'a, T> Finalize for RMut<'a, T> {
     unsafe fn finalize(&mut self) {}
 }
 
+// Similarly, `R` can't point into a `Gc`
+impl<T: ?Sized> GcClone for R<'_, T> {
+    #[inline]
+    fn gc_clone(&self, _: &mut GcCloner) -> Self {
+        *self
+    }
+}
+
 // Fixup the concurrency marker traits
 unsafe impl<'a, T: ?Sized> Send for R<'a, T> where &'a T: Send {}
 unsafe impl<'a, T: ?Sized> Sync for R<'a, T> where &'a T: Sync {}
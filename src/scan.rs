@@ -111,15 +111,16 @@ unsafe impl<T: Scan + Sized + 'static> ToScan for T {
 /// Scanner is a struct used to manage the scanning of data, sort of analogous to `Hasher`
 /// Usually you will only care about this while implementing `Scan`
 pub struct Scanner<'a> {
-    pub(crate) scan_callback: Box<dyn FnMut(InternalGcRef) + 'a>,
+    // Borrowed rather than boxed, since we make a `Scanner` for every piece of data we scan
+    pub(crate) scan_callback: &'a mut (dyn FnMut(InternalGcRef) + 'a),
 }
 
 #[allow(clippy::unused_self)]
 impl<'a> Scanner<'a> {
     #[must_use]
-    pub(crate) fn new<F: FnMut(InternalGcRef) + 'a>(callback: F) -> Self {
+    pub(crate) fn new<F: FnMut(InternalGcRef) + 'a>(callback: &'a mut F) -> Self {
         Self {
-            scan_callback: Box::new(callback),
+            scan_callback: callback,
         }
     }
 
@@ -0,0 +1,361 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Formatter};
+use std::marker::PhantomData;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use serde::de::{self, DeserializeSeed, EnumAccess, SeqAccess, VariantAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::atomic::{AtomicGc, AtomicOptionGc};
+use crate::collector::{GcData, InternalGcRef};
+use crate::marker::{GcDeref, GcDrop};
+use crate::{DerefGc, Gc, Scan};
+
+const GC_NAME: &str = "Gc";
+const DEF_VARIANT: &str = "Def";
+const REF_VARIANT: &str = "Ref";
+const VARIANTS: &[&str] = &[DEF_VARIANT, REF_VARIANT];
+
+/// Wraps a value, so everything inside it is (de)serialized as a single `Gc` graph
+///
+/// Each `Gc`, `DerefGc`, `AtomicGc` or (non-null) `AtomicOptionGc` is written as a tagged enum.
+/// The first time a piece of data is seen it's written in full as `Def(id, data)`. Every later `Gc`
+/// to that same data is written as `Ref(id)`. When deserializing, a `Ref` gives back a `Gc` to the
+/// data created by the matching `Def`, so sharing and cycles come back exactly as they were.
+///
+/// Ids are only meaningful within one graph. A `Gc` serialized on its own already starts a graph,
+/// so you only need `GcGraph` when sibling `Gc`s (like the elements of a `Vec<Gc<T>>`) should
+/// share ids.
+///
+/// # Example
+/// ```
+/// use std::cell::RefCell;
+///
+/// use shredder::serde_graph::GcGraph;
+/// use shredder::Gc;
+///
+/// let shared = Gc::new(RefCell::new(7_u32));
+/// let list = vec![shared.clone(), shared];
+///
+/// let json = serde_json::to_string(&GcGraph(&list)).unwrap();
+/// let GcGraph(copy): GcGraph<Vec<Gc<RefCell<u32>>>> = serde_json::from_str(&json).unwrap();
+///
+/// assert!(copy[0].ptr_eq(&copy[1]));
+/// assert_eq!(*copy[0].borrow(), 7);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GcGraph<T>(pub T);
+
+impl<T: Serialize> Serialize for GcGraph<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let _session = SerializeSession::start();
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for GcGraph<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let _session = DeserializeSession::start();
+        T::deserialize(deserializer).map(GcGraph)
+    }
+}
+
+/// ids given out to data, keyed by the data (holding onto the `Arc`s keeps the pointers we use as
+/// keys from being reused)
+type SerializeIds = HashMap<*const GcData, (u64, Arc<GcData>)>;
+
+thread_local! {
+    /// ids given out to data during the current serialization
+    static SERIALIZE_IDS: RefCell<Option<SerializeIds>> = const { RefCell::new(None) };
+    /// `Gc<T>`s created during the current deserialization, keyed by id
+    static DESERIALIZED: RefCell<Option<HashMap<u64, Box<dyn Any>>>> = const { RefCell::new(None) };
+}
+
+/// Sets up the serialization state, and clears it on drop if this was the outermost session
+struct SerializeSession {
+    is_outermost: bool,
+}
+
+impl SerializeSession {
+    fn start() -> Self {
+        let is_outermost = SERIALIZE_IDS.with(|ids| {
+            let mut ids = ids.borrow_mut();
+            let is_outermost = ids.is_none();
+            if is_outermost {
+                *ids = Some(HashMap::new());
+            }
+            is_outermost
+        });
+
+        Self { is_outermost }
+    }
+}
+
+impl Drop for SerializeSession {
+    fn drop(&mut self) {
+        if self.is_outermost {
+            // Take the map out first, so the `Arc`s are dropped after we release the `RefCell`
+            let ids = SERIALIZE_IDS.with(|ids| ids.borrow_mut().take());
+            drop(ids);
+        }
+    }
+}
+
+/// Same as `SerializeSession`, but for deserialization
+struct DeserializeSession {
+    is_outermost: bool,
+}
+
+impl DeserializeSession {
+    fn start() -> Self {
+        let is_outermost = DESERIALIZED.with(|gcs| {
+            let mut gcs = gcs.borrow_mut();
+            let is_outermost = gcs.is_none();
+            if is_outermost {
+                *gcs = Some(HashMap::new());
+            }
+            is_outermost
+        });
+
+        Self { is_outermost }
+    }
+}
+
+impl Drop for DeserializeSession {
+    fn drop(&mut self) {
+        if self.is_outermost {
+            // Take the map out first, so the `Gc`s are dropped after we release the `RefCell`
+            let gcs = DESERIALIZED.with(|gcs| gcs.borrow_mut().take());
+            drop(gcs);
+        }
+    }
+}
+
+/// Serialize the data behind `handle`, or a reference to it if it's already been written
+fn serialize_gc_data<S, T>(
+    handle: &InternalGcRef,
+    data: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize + ?Sized,
+{
+    let _session = SerializeSession::start();
+
+    let data_arc = handle.data();
+    let (id, is_new) = SERIALIZE_IDS.with(|ids| {
+        let mut ids = ids.borrow_mut();
+        let ids = ids.as_mut().expect("serialization state must be set up");
+
+        let next_id = ids.len() as u64;
+        let (id, _) = ids
+            .entry(Arc::as_ptr(data_arc))
+            .or_insert_with(|| (next_id, data_arc.clone()));
+        (*id, *id == next_id)
+    });
+
+    if is_new {
+        serializer.serialize_newtype_variant(GC_NAME, 0, DEF_VARIANT, &(id, data))
+    } else {
+        serializer.serialize_newtype_variant(GC_NAME, 1, REF_VARIANT, &id)
+    }
+}
+
+impl<T: Scan + Serialize + ?Sized> Serialize for Gc<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data = self.get();
+        serialize_gc_data(self.internal_handle_ref(), &*data, serializer)
+    }
+}
+
+impl<T: Scan + GcDeref + Serialize + ?Sized> Serialize for DerefGc<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_gc_data(self.internal_handle_ref(), &**self, serializer)
+    }
+}
+
+impl<T: Scan + Serialize> Serialize for AtomicGc<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.load(Ordering::SeqCst).serialize(serializer)
+    }
+}
+
+impl<T: Scan + Serialize> Serialize for AtomicOptionGc<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.load(Ordering::SeqCst).serialize(serializer)
+    }
+}
+
+/// Deserialize a `Gc<T>`, creating it if this is a `Def` or looking it up if this is a `Ref`
+fn deserialize_gc<'de, D, T>(deserializer: D) -> Result<Gc<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Scan + GcDrop + Deserialize<'de> + 'static,
+{
+    let _session = DeserializeSession::start();
+    deserializer.deserialize_enum(GC_NAME, VARIANTS, GcVisitor(PhantomData))
+}
+
+struct GcVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for GcVisitor<T>
+where
+    T: Scan + GcDrop + Deserialize<'de> + 'static,
+{
+    type Value = Gc<T>;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a Gc definition or reference")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        let (variant, access) = data.variant::<GcVariant>()?;
+        match variant {
+            GcVariant::Def => access.newtype_variant_seed(GcDefSeed(PhantomData)),
+            GcVariant::Ref => {
+                let id: u64 = access.newtype_variant()?;
+                DESERIALIZED.with(|gcs| {
+                    let gcs = gcs.borrow();
+                    let gcs = gcs.as_ref().expect("deserialization state must be set up");
+
+                    let gc = gcs
+                        .get(&id)
+                        .ok_or_else(|| de::Error::custom(format!("unknown Gc id {id}")))?;
+                    gc.downcast_ref::<Gc<T>>().cloned().ok_or_else(|| {
+                        de::Error::custom(format!("Gc id {id} refers to data of a different type"))
+                    })
+                })
+            }
+        }
+    }
+}
+
+/// Deserializes the `(id, data)` pair inside a `Def`
+struct GcDefSeed<T>(PhantomData<T>);
+
+impl<'de, T> DeserializeSeed<'de> for GcDefSeed<T>
+where
+    T: Scan + GcDrop + Deserialize<'de> + 'static,
+{
+    type Value = Gc<T>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_tuple(2, self)
+    }
+}
+
+impl<'de, T> Visitor<'de> for GcDefSeed<T>
+where
+    T: Scan + GcDrop + Deserialize<'de> + 'static,
+{
+    type Value = Gc<T>;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a Gc id followed by its data")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let id: u64 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+        // The data may refer back to itself, so the `Gc` needs to exist before the data does
+        Gc::new_cyclic_fallible(|gc| {
+            let already_defined = DESERIALIZED.with(|gcs| {
+                let mut gcs = gcs.borrow_mut();
+                let gcs = gcs.as_mut().expect("deserialization state must be set up");
+                gcs.insert(id, Box::new(gc)).is_some()
+            });
+            if already_defined {
+                return Err(de::Error::custom(format!("Gc id {id} is defined twice")));
+            }
+
+            seq.next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))
+        })
+    }
+}
+
+/// The two ways a `Gc` can be written out
+enum GcVariant {
+    Def,
+    Ref,
+}
+
+impl<'de> Deserialize<'de> for GcVariant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_identifier(GcVariantVisitor)
+    }
+}
+
+struct GcVariantVisitor;
+
+impl Visitor<'_> for GcVariantVisitor {
+    type Value = GcVariant;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "`{DEF_VARIANT}` or `{REF_VARIANT}`")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        match v {
+            0 => Ok(GcVariant::Def),
+            1 => Ok(GcVariant::Ref),
+            _ => Err(de::Error::invalid_value(de::Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        match v {
+            DEF_VARIANT => Ok(GcVariant::Def),
+            REF_VARIANT => Ok(GcVariant::Ref),
+            _ => Err(de::Error::unknown_variant(v, VARIANTS)),
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Gc<T>
+where
+    T: Scan + GcDrop + Deserialize<'de> + 'static,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_gc(deserializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for DerefGc<T>
+where
+    T: Scan + GcDeref + GcDrop + Deserialize<'de> + 'static,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Built on the `Gc<T>` path, so `Gc`s and `DerefGc`s to the same data share an id
+        let gc: Gc<T> = deserialize_gc(deserializer)?;
+        Ok(Self::from(gc))
+    }
+}
+
+impl<'de, T> Deserialize<'de> for AtomicGc<T>
+where
+    T: Scan + GcDrop + Deserialize<'de> + 'static,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let gc: Gc<T> = deserialize_gc(deserializer)?;
+        // The data may still be under construction if we're in a cycle
+        Ok(Self::new_unchecked(&gc))
+    }
+}
+
+impl<'de, T> Deserialize<'de> for AtomicOptionGc<T>
+where
+    T: Scan + GcDrop + Deserialize<'de> + 'static,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let gc: Option<Gc<T>> = Option::deserialize(deserializer)?;
+        // The data may still be under construction if we're in a cycle
+        Ok(Self::new_unchecked(gc.as_ref()))
+    }
+}
@@ -5,17 +5,28 @@ use std::any::{Any, TypeId};
 use std::cmp::Ordering;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
+use std::mem::ManuallyDrop;
 use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::atomic;
 use std::{fmt, ptr};
 
 use crate::collector::{InternalGcRef, COLLECTOR};
 use crate::marker::{GcDeref, GcDrop, GcSafe};
-use crate::{Finalize, Scan, Scanner, ToScan};
+use crate::{Finalize, Gc, Scan, Scanner, ToScan};
 
 /// A `Gc`, but with the ability to `Deref` to its contents!
 ///
 /// This comes with the requirement that your data implement `GcDeref`, which can be limiting. See
 /// `GcDeref` documentation for details.
+///
+/// A `Gc<T>` can be turned into a `DerefGc<T>` with `DerefGc::from` (and back with
+/// `DerefGc::into_gc`), without allocating anything new.
+///
+/// Like with a `Gc`, the data in a `DerefGc` can only be moved by its owner (by turning it into a
+/// `Gc` with `into_gc`). A `Pin<DerefGc<T>>` only gives out shared references to the data, and
+/// only gives back the `DerefGc` itself if `T: Unpin`, so it upholds the `Pin` guarantee. Use
+/// `DerefGc::pin` to create one.
 pub struct DerefGc<T: Scan + GcDeref + ?Sized> {
     backing_handle: InternalGcRef,
     direct_ptr: *const T,
@@ -83,6 +94,10 @@ impl<T: Scan + GcDeref + ?Sized> DerefGc<T> {
     /// This function does not allocate anything - rather, it uses the `Box<T>` and releases its
     /// memory appropriately. This is useful since it removes the requirement for types to be
     /// sized.
+    ///
+    /// There's no cyclic version of this, since the value already exists by the time it's boxed.
+    /// For sized data, `new_cyclic` does the same job (and can then be coerced to an unsized
+    /// `DerefGc` with the `nightly-features` feature).
     pub fn from_box(v: Box<T>) -> Self
     where
         T: ToScan + GcDrop,
@@ -94,6 +109,78 @@ impl<T: Scan + GcDeref + ?Sized> DerefGc<T> {
         }
     }
 
+    /// Create a new `DerefGc`, initializing the value inside the `DerefGc` with a supplied closure.
+    ///
+    /// This closure is given a self-referential `DerefGc`--so this function can be used to create
+    /// self-referential data.
+    ///
+    /// If the supplied self-referential `DerefGc` is dereferenced before the closure is complete,
+    /// the access will panic
+    ///
+    /// Similar to `new` in that the supplied data's destructor will be run when the garbage
+    /// collector deallocates it.
+    pub fn new_cyclic<F>(f: F) -> Self
+    where
+        T: Sized + GcDrop,
+        F: FnOnce(Self) -> T,
+    {
+        Gc::new_cyclic(|gc| f(Self::from(gc))).into()
+    }
+
+    /// Create a new `DerefGc`, initializing the value inside the `DerefGc` with a supplied closure
+    /// (But specifying not to run its destructor.)
+    ///
+    /// See `new_cyclic` and `new_no_drop`. Be careful using this method! It can lead to memory
+    /// leaks!
+    pub fn new_cyclic_no_drop<F>(f: F) -> Self
+    where
+        T: Sized,
+        F: FnOnce(Self) -> T,
+    {
+        Gc::new_cyclic_no_drop(|gc| f(Self::from(gc))).into()
+    }
+
+    /// Create a new `DerefGc`, initializing the value inside the `DerefGc` with a supplied closure
+    /// (But specifying to call `finalize` on it instead of running its destructor.)
+    ///
+    /// See `new_cyclic` and `new_with_finalizer`
+    pub fn new_cyclic_with_finalizer<F>(f: F) -> Self
+    where
+        T: Sized + Finalize,
+        F: FnOnce(Self) -> T,
+    {
+        Gc::new_cyclic_with_finalizer(|gc| f(Self::from(gc))).into()
+    }
+
+    /// Create a new `Pin<DerefGc<T>>` containing the given data.
+    ///
+    /// The data can't be moved while it's pinned, so if `T: !Unpin` it is safe to rely on its
+    /// address staying the same until it is dropped. Otherwise this is the same as `DerefGc::new`.
+    pub fn pin(v: T) -> Pin<Self>
+    where
+        T: Sized + GcDrop,
+    {
+        // Safety: Moving the data out needs an unpinned `DerefGc` (to call `into_gc`), which a
+        // `Pin<DerefGc>` only gives back if `T: Unpin`. This one was never available unpinned
+        unsafe { Pin::new_unchecked(Self::new(v)) }
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn internal_handle_ref(&self) -> &InternalGcRef {
+        &self.backing_handle
+    }
+
+    /// Turn this `DerefGc` into a `Gc` pointing to the same data
+    ///
+    /// This is the reverse of `DerefGc::from(gc)`. Neither direction allocates.
+    #[must_use]
+    pub fn into_gc(self) -> Gc<T> {
+        let this = ManuallyDrop::new(self);
+        // Safe, since `this` is never used (or dropped) again
+        let backing_handle = unsafe { ptr::read(ptr::addr_of!(this.backing_handle)) };
+        Gc::new_raw(backing_handle, this.direct_ptr)
+    }
+
     /// `ptr_eq` lets you compare two `DerefGc`s for pointer equality.
     ///
     /// This has the same semantics as `ptr::eq` or `Arc::ptr_eq`.
@@ -164,10 +251,32 @@ impl<T: Scan + GcDeref + ?Sized> Deref for DerefGc<T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
+        // Only the `DerefGc` given to a `new_cyclic` closure can see data that isn't initialized yet.
+        // Acquire pairs with the release once the data is written, so we see the initialized value
+        let is_deallocated = self
+            .backing_handle
+            .data()
+            .deallocated
+            .load(atomic::Ordering::Acquire);
+        assert!(
+            !is_deallocated,
+            "Tried to deref a DerefGc whose data is not initialized (or was deallocated)"
+        );
+
         unsafe { &*self.direct_ptr }
     }
 }
 
+impl<T: Scan + GcDeref + ?Sized> From<Gc<T>> for DerefGc<T> {
+    fn from(gc: Gc<T>) -> Self {
+        let (backing_handle, direct_ptr) = gc.into_raw();
+        Self {
+            backing_handle,
+            direct_ptr,
+        }
+    }
+}
+
 impl<T: Scan + GcDeref + ?Sized> Drop for DerefGc<T> {
     fn drop(&mut self) {
         self.backing_handle.invalidate()
@@ -4,27 +4,45 @@ use std::cell::{BorrowError, BorrowMutError, RefCell};
 use std::cmp::Ordering;
 use std::fmt::{self, Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
-use std::ops::Deref;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
 use std::sync::atomic;
 #[cfg(feature = "nightly-features")]
 use std::{marker::Unsize, ops::CoerceUnsized};
-use std::{ptr, sync};
+use std::{mem, ptr, sync};
 
 use stable_deref_trait::StableDeref;
 
-use crate::collector::{GcGuardWarrant, InternalGcRef, COLLECTOR};
+use crate::cell::{GcCell, GcCellRef, GcCellRefMut};
+use crate::collector::{
+    AllocError, GcExclusiveGuardWarrant, GcGuardWarrant, InternalGcRef, COLLECTOR,
+};
 use crate::marker::{GcDeref, GcDrop, GcSafe};
+use crate::smart_ptr::{pin_pointer, pinned_pointer};
+use crate::visit::{fmt_cycle_aware, fmt_opaque};
 use crate::wrappers::{
     GcMutexGuard, GcPoisonError, GcRef, GcRefMut, GcRwLockReadGuard, GcRwLockWriteGuard,
     GcTryLockError,
 };
-use crate::{Finalize, Scan, Scanner, ToScan};
+use crate::{Finalize, GcClone, GcCloner, Scan, Scanner, ToScan};
 
 /// A smart-pointer for data tracked by `shredder` garbage collector
 ///
 /// This lets you store arbitrary `Scan` data on the heap, even if that data has cycles. If you
 /// want to understand `Scan` and implement it for your type, see the documentation of `Scan` and
 /// the provided `Scan` derive.
+///
+/// # Pinning
+/// The collector never moves data, it only drops (or finalizes) it in place. Data can only be moved
+/// out by whoever owns a `Gc` to it, with `try_unwrap`, or through `get_mut` or `make_mut`. All of
+/// those need the `Gc` itself, or `&mut` to it.
+///
+/// A `Pin<Gc<T>>` never gives out either. `Gc` doesn't implement `Deref`, so `Pin::into_inner`,
+/// `Pin::get_mut` and `Pin::as_mut` can't be used on it, and cloning a pinned `Gc` gives back
+/// another pinned `Gc`. So as long as every `Gc` to the data is pinned from the start (which is
+/// what `Gc::pin` does) the data can't be moved until it's dropped, and `Pin<Gc<T>>` upholds the
+/// `Pin` guarantee. This means a `Gc` can hold self-referential or intrusive data, and futures.
+/// Use `Gc::pin` to create one, and `Gc::get_pinned` to get at the data.
 pub struct Gc<T: Scan + ?Sized> {
     backing_handle: InternalGcRef,
     direct_ptr: *const T,
@@ -52,6 +70,29 @@ impl<T: Scan + ?Sized> Gc<T> {
         }
     }
 
+    /// Create a new `Gc` containing the given data, or give back an error if there isn't enough
+    /// memory.
+    ///
+    /// This is `new`, but instead of aborting the process when memory runs out, it runs an
+    /// emergency `collect` (and waits for the freed data's destructors), then tries once more. So
+    /// like `collect`, avoid calling it while holding a `GcGuard`, or from inside a destructor.
+    ///
+    /// Only the allocation for `v` itself is fallible. The collector's own bookkeeping for it is a
+    /// couple of small `Arc`s, and those still abort the process if they can't be allocated.
+    ///
+    /// # Errors
+    /// Returns `AllocError` if there still isn't enough memory after collecting. (`v` is dropped.)
+    pub fn try_new(v: T) -> Result<Self, AllocError>
+    where
+        T: Sized + GcDrop,
+    {
+        let (handle, ptr) = COLLECTOR.try_track_with_drop(v)?;
+        Ok(Self {
+            backing_handle: handle,
+            direct_ptr: ptr,
+        })
+    }
+
     /// Create a new `Gc` containing the given data. (But specifying not to run its destructor.)
     /// This is useful because `T: GcDrop` is no longer necessary!
     ///
@@ -68,6 +109,55 @@ impl<T: Scan + ?Sized> Gc<T> {
         }
     }
 
+    /// Create a new `Gc` containing the given data, whose destructor runs on this thread.
+    ///
+    /// Usually data is destroyed on a background thread, which is why `Gc::new` needs `T: GcDrop`.
+    /// Data created with this method is instead sent back to the thread that created it once it's
+    /// garbage. It's destroyed when that thread calls `run_local_destructors`, or next allocates a
+    /// `Gc`. So `T` doesn't need to be `GcDrop`, which makes this suitable for data that has to be
+    /// dropped where it was made, such as UI handles or things holding `Rc`s.
+    ///
+    /// `T` still has to be `Scan`, since the collector scans it in the background. A field the
+    /// collector must never look at (like an `Rc` that doesn't lead to any `Gc`s) can be left out
+    /// with `#[shredder(unsafe_skip_gc_safe)]`.
+    ///
+    /// If this thread exits before the data is destroyed, its destructor never runs (and its
+    /// memory is leaked).
+    ///
+    /// # Example
+    /// ```
+    /// use std::rc::Rc;
+    /// use shredder::{collect, run_local_destructors, synchronize_destructors, Gc, Scan};
+    ///
+    /// #[derive(Scan)]
+    /// #[shredder(cant_drop)]
+    /// struct Widget {
+    ///     // The collector never touches this, so it's fine that only this thread may
+    ///     #[shredder(unsafe_skip_gc_safe)]
+    ///     shared: Rc<String>,
+    /// }
+    ///
+    /// let shared = Rc::new(String::from("toolkit state"));
+    /// let widget = Gc::new_local(Widget { shared: shared.clone() });
+    /// assert_eq!(Rc::strong_count(&shared), 2);
+    ///
+    /// drop(widget);
+    /// collect();
+    /// synchronize_destructors();
+    /// run_local_destructors();
+    /// assert_eq!(Rc::strong_count(&shared), 1);
+    /// ```
+    pub fn new_local(v: T) -> Self
+    where
+        T: Sized + 'static,
+    {
+        let (handle, ptr) = COLLECTOR.track_with_local_drop(v);
+        Self {
+            backing_handle: handle,
+            direct_ptr: ptr,
+        }
+    }
+
     /// Create a new `Gc` containing the given data. (But specifying to call `finalize` on it
     /// instead of running its destructor.)
     ///
@@ -96,6 +186,13 @@ impl<T: Scan + ?Sized> Gc<T> {
     /// This function does not allocate anything - rather, it uses the `Box<T>` and releases its
     /// memory appropriately. This is useful since it removes the requirement for types to be
     /// sized.
+    ///
+    /// There's no cyclic version of this, since the value already exists by the time it's boxed.
+    /// For sized data, `new_cyclic` does the same job (and can then be coerced to an unsized
+    /// `Gc` with the `nightly-features` feature).
+    ///
+    /// There's no fallible version either. The box is already allocated, and only the allocation
+    /// for the data itself can fail gracefully (see `try_new`).
     pub fn from_box(v: Box<T>) -> Self
     where
         T: ToScan + GcDrop,
@@ -107,6 +204,20 @@ impl<T: Scan + ?Sized> Gc<T> {
         }
     }
 
+    /// Create a new `Pin<Gc<T>>` containing the given data.
+    ///
+    /// The data can't be moved while it's pinned (see "Pinning" above), so if `T: !Unpin` it is safe
+    /// to rely on its address staying the same until it is dropped. Otherwise this is the same as
+    /// `Gc::new`.
+    pub fn pin(v: T) -> Pin<Self>
+    where
+        T: Sized + GcDrop,
+    {
+        // Safety: Moving the data out needs an unpinned `Gc` (or `&mut` to one), which a `Pin<Gc>`
+        // never gives out. This is the only `Gc` to the new data, and it's never available unpinned
+        unsafe { pin_pointer(Self::new(v)) }
+    }
+
     /// Create a new `Gc`, initializing the value inside the `Gc` with a supplied closure.
     ///
     /// This closure is given a self-referential `Gc`--so this function can be used to create
@@ -122,31 +233,47 @@ impl<T: Scan + ?Sized> Gc<T> {
         T: Sized + GcDrop,
         F: FnOnce(Self) -> T,
     {
-        let (handle, ptr) = unsafe {
-            COLLECTOR.track_with_initializer(move |gc_ref, uninit_ptr: *const T| {
-                // Mark the data as deallocated, so the caller can't access it
-                gc_ref
-                    .data()
-                    .deallocated
-                    .store(true, atomic::Ordering::Relaxed);
-
-                // Create a Gc<T>
-                let gc = Self {
-                    backing_handle: gc_ref.clone(),
-                    direct_ptr: uninit_ptr,
-                };
+        let (handle, ptr) =
+            unsafe { COLLECTOR.track_with_initializer(Self::cyclic_initializer(f)) };
 
-                let res = f(gc);
+        Self {
+            backing_handle: handle,
+            direct_ptr: ptr,
+        }
+    }
 
-                // Unmark the data as deallocated, so that things can proceed normally
-                gc_ref
-                    .data()
-                    .deallocated
-                    .store(false, atomic::Ordering::Relaxed);
+    /// Like `new_cyclic`, but gives back an error if there isn't enough memory.
+    ///
+    /// See `try_new` for how running out is handled (and what allocation can still abort). If memory
+    /// runs out, `f` is never called.
+    ///
+    /// # Errors
+    /// Returns `AllocError` if there still isn't enough memory after collecting.
+    pub fn try_new_cyclic<F>(f: F) -> Result<Self, AllocError>
+    where
+        T: Sized + GcDrop,
+        F: FnOnce(Self) -> T,
+    {
+        let (handle, ptr) =
+            unsafe { COLLECTOR.try_track_with_initializer(Self::cyclic_initializer(f))? };
 
-                res
-            })
-        };
+        Ok(Self {
+            backing_handle: handle,
+            direct_ptr: ptr,
+        })
+    }
+
+    /// Create a new `Gc`, initializing the value inside the `Gc` with a supplied closure. (But
+    /// specifying not to run its destructor.)
+    ///
+    /// See `new_cyclic` and `new_no_drop`. Be careful using this method! It can lead to memory leaks!
+    pub fn new_cyclic_no_drop<F>(f: F) -> Self
+    where
+        T: Sized,
+        F: FnOnce(Self) -> T,
+    {
+        let (handle, ptr) =
+            unsafe { COLLECTOR.track_with_initializer_no_drop(Self::cyclic_initializer(f)) };
 
         Self {
             backing_handle: handle,
@@ -160,39 +287,63 @@ impl<T: Scan + ?Sized> Gc<T> {
     /// See `new_cyclic` and `new_with_finalizer`
     pub fn new_cyclic_with_finalizer<F>(f: F) -> Self
     where
-        T: Sized + Finalize, // FIXME: Add a `GcDrop` variant
+        T: Sized + Finalize,
+        F: FnOnce(Self) -> T,
+    {
+        let (handle, ptr) =
+            unsafe { COLLECTOR.track_with_initializer_and_finalize(Self::cyclic_initializer(f)) };
+
+        Self {
+            backing_handle: handle,
+            direct_ptr: ptr,
+        }
+    }
+
+    /// Turn a `new_cyclic` style closure into an initializer for the collector
+    ///
+    /// The collector marks the data as deallocated while `f` runs (and until the value `f` returns
+    /// has been written), so the `Gc` it's given can't be used yet
+    fn cyclic_initializer<F>(f: F) -> impl FnOnce(InternalGcRef, *const T) -> T
+    where
+        T: Sized,
         F: FnOnce(Self) -> T,
+    {
+        move |gc_ref, uninit_ptr| {
+            // Create a Gc<T>
+            let gc = Self {
+                backing_handle: gc_ref,
+                direct_ptr: uninit_ptr,
+            };
+
+            f(gc)
+        }
+    }
+
+    /// Like `new_cyclic`, but the closure may fail, in which case nothing is allocated
+    ///
+    /// Any copies of the self-referential `Gc` that outlive a failed closure will panic on access
+    #[cfg(feature = "serde")]
+    pub(crate) fn new_cyclic_fallible<E, F>(f: F) -> Result<Self, E>
+    where
+        T: Sized + GcDrop,
+        F: FnOnce(Self) -> Result<T, E>,
     {
         let (handle, ptr) = unsafe {
-            COLLECTOR.track_with_initializer_and_finalize(move |gc_ref, uninit_ptr: *const T| {
-                // Mark the data as deallocated, so the caller can't access it
-                gc_ref
-                    .data()
-                    .deallocated
-                    .store(true, atomic::Ordering::Relaxed);
-
-                // Create a Gc<T>
+            COLLECTOR.track_with_fallible_initializer(move |gc_ref, uninit_ptr: *const T| {
+                // Create a Gc<T> (the collector won't let it be used until the data is initialized)
                 let gc = Self {
-                    backing_handle: gc_ref.clone(),
+                    backing_handle: gc_ref,
                     direct_ptr: uninit_ptr,
                 };
 
-                let res = f(gc);
-
-                // Unmark the data as deallocated, so that things can proceed normally
-                gc_ref
-                    .data()
-                    .deallocated
-                    .store(false, atomic::Ordering::Relaxed);
-
-                res
-            })
+                f(gc)
+            })?
         };
 
-        Self {
+        Ok(Self {
             backing_handle: handle,
             direct_ptr: ptr,
-        }
+        })
     }
 
     pub(crate) fn new_raw(backing_handle: InternalGcRef, direct_ptr: *const T) -> Self {
@@ -202,6 +353,14 @@ impl<T: Scan + ?Sized> Gc<T> {
         }
     }
 
+    /// Take apart this `Gc` without dropping its handle (see `new_raw`)
+    pub(crate) fn into_raw(self) -> (InternalGcRef, *const T) {
+        let this = mem::ManuallyDrop::new(self);
+        // Safe, since `this` is never used (or dropped) again
+        let backing_handle = unsafe { ptr::read(ptr::addr_of!(this.backing_handle)) };
+        (backing_handle, this.direct_ptr)
+    }
+
     /// `get` lets you get a `GcGuard`, which will deref to the underlying data.
     ///
     /// `get` is used to get a `GcGuard`. This is usually what you want when accessing non-`Sync`
@@ -218,6 +377,113 @@ impl<T: Scan + ?Sized> Gc<T> {
         }
     }
 
+    /// `get_pinned` is the `Pin` equivalent of `get`, giving a pinned `GcGuard` to the data.
+    ///
+    /// Since a `GcGuard` derefs to the data, `Pin::as_ref` can then be used to get a `Pin<&T>`.
+    #[must_use]
+    pub fn get_pinned(this: &Pin<Self>) -> Pin<GcGuard<'_, T>> {
+        let guard = pinned_pointer(this).get();
+        // Safety: The guard points to the same (pinned) data as `this`
+        unsafe { Pin::new_unchecked(guard) }
+    }
+
+    /// `try_unwrap` moves the data out of this `Gc`, if this is the only `Gc` pointing to it.
+    ///
+    /// This fails, giving back the `Gc`, if any other `Gc`, `DerefGc` or `AtomicGc` points to the
+    /// same data (even one inside garbage that hasn't been collected yet), or if the data is
+    /// currently in use. Running `collect` first can help if garbage may be pointing at the data.
+    ///
+    /// This briefly blocks collection. If the data has ever been stored in an `AtomicGc`, checking
+    /// also means looking at every handle the collector knows about, which is not cheap! Inside a
+    /// destructor, this fails (rather than waiting) if a collection is running.
+    ///
+    /// No destructor or finalizer is run on success, since the data is now yours.
+    ///
+    /// # Errors
+    /// Gives back this `Gc` if it isn't the only `Gc` pointing to its data.
+    pub fn try_unwrap(self) -> Result<T, Self>
+    where
+        T: Sized,
+    {
+        if !COLLECTOR.try_untrack_unique(&self.backing_handle) {
+            return Err(self);
+        }
+
+        let allocation = self.backing_handle.data().underlying_allocation;
+        // Safe, since the collector has forgotten the data, and no other handle can reach it
+        unsafe {
+            let value = ptr::read(self.direct_ptr);
+            allocation.release_memory::<T>();
+            Ok(value)
+        }
+    }
+
+    /// `into_inner` is like `try_unwrap`, but gives back `None` (dropping this `Gc`) on failure.
+    #[must_use]
+    pub fn into_inner(self) -> Option<T>
+    where
+        T: Sized,
+    {
+        self.try_unwrap().ok()
+    }
+
+    /// `get_mut` gives mutable access to the data, if this is the only `Gc` pointing to it.
+    ///
+    /// This is like `Arc::get_mut`, and returns `None` if any other `Gc`, `DerefGc` or `AtomicGc`
+    /// points to the same data (even one inside garbage that hasn't been collected yet). The
+    /// returned `GcGuardMut` keeps the collector from scanning the data while you change it.
+    ///
+    /// This is usually cheap, since each piece of data counts its handles. But like `try_unwrap`, if
+    /// the data has ever been stored in an `AtomicGc`, checking means looking at every handle the
+    /// collector knows about. Inside a destructor that check fails (rather than waiting) if a
+    /// collection is running.
+    ///
+    /// It also returns `None` while anything else is looking at the data, even without a `Gc` to
+    /// it, like a running collection or `visit::visit_graph`.
+    #[must_use]
+    pub fn get_mut(&mut self) -> Option<GcGuardMut<'_, T>> {
+        if !COLLECTOR.is_unique(&self.backing_handle) {
+            return None;
+        }
+
+        let warrant = COLLECTOR.try_get_exclusive_data_warrant(&self.backing_handle)?;
+        Some(GcGuardMut {
+            gc_ptr: self,
+            _warrant: warrant,
+        })
+    }
+
+    /// `make_mut` gives mutable access to the data, cloning it into a new `Gc` first if it is
+    /// shared.
+    ///
+    /// This is like `Arc::make_mut`. If `get_mut` would return `None`, the data is cloned, and this
+    /// `Gc` is changed to point to the clone. (Any `Gc`s inside the data are shallowly cloned, so
+    /// they will point to the same data as before.)
+    ///
+    /// If a collection is running, this may wait for it to finish looking at the clone.
+    pub fn make_mut(&mut self) -> GcGuardMut<'_, T>
+    where
+        T: Clone + GcDrop,
+    {
+        let existing_warrant = if COLLECTOR.is_unique(&self.backing_handle) {
+            COLLECTOR.try_get_exclusive_data_warrant(&self.backing_handle)
+        } else {
+            None
+        };
+
+        let warrant = existing_warrant.unwrap_or_else(|| {
+            let copy = T::clone(&self.get());
+            *self = Self::new(copy);
+            // Nothing else can reach a brand new `Gc`, but a collection may be scanning it
+            COLLECTOR.wait_for_exclusive_data_warrant(&self.backing_handle)
+        });
+
+        GcGuardMut {
+            gc_ptr: self,
+            _warrant: warrant,
+        }
+    }
+
     /// `ptr_eq` lets you compare two `Gc`s for pointer equality.
     ///
     /// This has the same semantics as `ptr::eq` or `Arc::ptr_eq`.
@@ -241,6 +507,42 @@ impl<T: Scan + ?Sized> Gc<T> {
     }
 }
 
+impl<T: Scan + ?Sized> Gc<T> {
+    /// Get something that formats this `Gc` without looking at the data inside, as `Gc(ptr)`
+    ///
+    /// This is for when `T` isn't `Debug`, so the `Gc` itself can't be formatted. If it's printed
+    /// while a graph of `Gc`s is being formatted, it's tagged to match, as `Gc(#N, ptr)`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::fmt::{self, Debug, Formatter};
+    ///
+    /// use shredder::{Gc, Scan};
+    ///
+    /// #[derive(Scan)]
+    /// struct NotDebug;
+    ///
+    /// #[derive(Scan)]
+    /// struct Holder {
+    ///     inner: Gc<NotDebug>,
+    /// }
+    ///
+    /// impl Debug for Holder {
+    ///     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    ///         let inner = self.inner.debug_opaque();
+    ///         f.debug_struct("Holder").field("inner", &inner).finish()
+    ///     }
+    /// }
+    ///
+    /// let holder = Holder { inner: Gc::new(NotDebug) };
+    /// assert!(format!("{:?}", holder).starts_with("Holder { inner: Gc(0x"));
+    /// ```
+    #[must_use]
+    pub fn debug_opaque(&self) -> GcOpaque<'_, T> {
+        GcOpaque { gc: self }
+    }
+}
+
 impl<T: Scan + ?Sized> Gc<T> {
     /// Attempt to `downcast` this `Gc<T>` to a `Gc<S>`
     ///
@@ -290,6 +592,13 @@ unsafe impl<T: Scan + ?Sized> Scan for Gc<T> {
     }
 }
 
+// Deep clones share the `GcCloner`'s memoized copies, see `deep_clone`
+impl<T: GcClone + GcDrop + 'static> GcClone for Gc<T> {
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        cloner.clone_gc(self)
+    }
+}
+
 unsafe impl<T: Scan + ?Sized> GcSafe for Gc<T> {}
 unsafe impl<T: Scan + ?Sized> GcDrop for Gc<T> {}
 unsafe impl<T: Scan + Send + Sync + ?Sized> GcDeref for Gc<T> {}
@@ -322,12 +631,22 @@ unsafe impl<T: Scan + ?Sized> Finalize for Gc<T> {
 }
 
 // Lots of traits it's good for a smart ptr to implement:
-impl<T: Scan + ?Sized> Debug for Gc<T> {
+
+/// Formats the data inside the `Gc`, as `Gc(#N, ptr, data)`. `#N` tags the data, numbering it the
+/// same way `visit::visit_graph` does, and `ptr` is where it lives.
+///
+/// This is safe to use on cyclic data. If a `Gc` is reached again while its data is already being
+/// formatted, it's printed as `<cycle #N>`, where `#N` is the tag of the earlier occurrence. (If
+/// `T` isn't `Debug`, see `Gc::debug_opaque`.)
+impl<T: Scan + Debug + ?Sized> Debug for Gc<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Gc")
-            .field("backing_handle", &"<SNIP>")
-            .field("direct_ptr", &self.direct_ptr)
-            .finish()
+        fmt_cycle_aware(self, f, |v, id, f| {
+            f.debug_tuple("Gc")
+                .field(&format_args!("{id}"))
+                .field(&self.direct_ptr)
+                .field(&v)
+                .finish()
+        })
     }
 }
 
@@ -347,8 +666,7 @@ where
     T: Display,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let a = self.get();
-        a.fmt(f)
+        fmt_cycle_aware(self, f, |v, _, f| v.fmt(f))
     }
 }
 
@@ -447,6 +765,23 @@ where
     }
 }
 
+/// Formats a `Gc` without looking at the data inside it (see `Gc::debug_opaque`)
+pub struct GcOpaque<'a, T: Scan + ?Sized> {
+    gc: &'a Gc<T>,
+}
+
+impl<T: Scan + ?Sized> Debug for GcOpaque<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_opaque(self.gc, f, |id, f| {
+            let mut tuple = f.debug_tuple("Gc");
+            if let Some(id) = id {
+                tuple.field(&format_args!("{id}"));
+            }
+            tuple.field(&self.gc.direct_ptr).finish()
+        })
+    }
+}
+
 /// A guard object that lets you access the underlying data of a `Gc`.
 /// It exists as data needs protection from being scanned while it's being concurrently modified.
 pub struct GcGuard<'a, T: Scan + ?Sized> {
@@ -489,6 +824,50 @@ impl<'a, T: Scan + Debug> Debug for GcGuard<'a, T> {
     }
 }
 
+/// A guard object that lets you mutate the underlying data of a `Gc`.
+/// You can get one from `Gc::get_mut` or `Gc::make_mut`, which ensure the `Gc` is unique.
+pub struct GcGuardMut<'a, T: Scan + ?Sized> {
+    gc_ptr: &'a mut Gc<T>,
+    _warrant: GcExclusiveGuardWarrant,
+}
+
+impl<T: Scan + ?Sized> Deref for GcGuardMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.gc_ptr.direct_ptr }
+    }
+}
+
+impl<T: Scan + ?Sized> DerefMut for GcGuardMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // Safe since this is the only handle, we hold it mutably, and our warrant is exclusive (so
+        // nothing else can be scanning the data, see `Gc::get_mut`)
+        unsafe { &mut *self.gc_ptr.direct_ptr.cast_mut() }
+    }
+}
+
+impl<T: Scan + ?Sized> AsRef<T> for GcGuardMut<'_, T> {
+    fn as_ref(&self) -> &T {
+        self.deref()
+    }
+}
+
+impl<T: Scan + ?Sized> AsMut<T> for GcGuardMut<'_, T> {
+    fn as_mut(&mut self) -> &mut T {
+        self.deref_mut()
+    }
+}
+
+impl<T: Scan + Debug> Debug for GcGuardMut<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GcGuardMut")
+            .field("v", self.deref())
+            .field("warrant", &"<SNIP>")
+            .finish()
+    }
+}
+
 // Special casing goes here, mostly so rustdoc documents it in the right order
 impl<T: Scan + 'static> Gc<RefCell<T>> {
     /// Call the underlying `borrow` method on the `RefCell`.
@@ -531,6 +910,79 @@ impl<T: Scan + 'static> Gc<RefCell<T>> {
     }
 }
 
+impl<T: Scan + 'static> Gc<GcCell<T>> {
+    /// Call the underlying `borrow` method on the `GcCell`.
+    ///
+    /// # Panics
+    /// Panics if the value is currently mutably borrowed.
+    #[must_use]
+    pub fn borrow(&self) -> GcCellRef<'_, T> {
+        let warrant = COLLECTOR.get_data_warrant(&self.backing_handle);
+        self.cell().borrow_with(Some(warrant))
+    }
+
+    /// Call the underlying `try_borrow` method on the `GcCell`.
+    ///
+    /// # Errors
+    /// Returns an error if the value is currently mutably borrowed.
+    pub fn try_borrow(&self) -> Result<GcCellRef<'_, T>, BorrowError> {
+        let warrant = COLLECTOR.get_data_warrant(&self.backing_handle);
+        self.cell().try_borrow_with(Some(warrant))
+    }
+
+    /// Call the underlying `borrow_mut` method on the `GcCell`.
+    ///
+    /// # Panics
+    /// Panics if the value is currently borrowed.
+    #[must_use]
+    pub fn borrow_mut(&self) -> GcCellRefMut<'_, T> {
+        let warrant = COLLECTOR.get_data_warrant(&self.backing_handle);
+        self.cell().borrow_mut_with(Some(warrant))
+    }
+
+    /// Call the underlying `try_borrow_mut` method on the `GcCell`.
+    ///
+    /// # Errors
+    /// Returns an error if the value is currently borrowed.
+    pub fn try_borrow_mut(&self) -> Result<GcCellRefMut<'_, T>, BorrowMutError> {
+        let warrant = COLLECTOR.get_data_warrant(&self.backing_handle);
+        self.cell().try_borrow_mut_with(Some(warrant))
+    }
+
+    /// Call the underlying `replace` method on the `GcCell`.
+    ///
+    /// # Panics
+    /// Panics if the value is currently borrowed.
+    pub fn replace(&self, value: T) -> T {
+        mem::replace(&mut *self.borrow_mut(), value)
+    }
+
+    /// Call the underlying `swap` method on the `GcCell`.
+    ///
+    /// # Panics
+    /// Panics if either value is currently borrowed.
+    pub fn swap(&self, other: &Self) {
+        mem::swap(&mut *self.borrow_mut(), &mut *other.borrow_mut());
+    }
+
+    /// Call the underlying `take` method on the `GcCell`.
+    ///
+    /// # Panics
+    /// Panics if the value is currently borrowed.
+    #[must_use]
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        self.replace(T::default())
+    }
+
+    fn cell(&self) -> &GcCell<T> {
+        // Safe, since we only ever access the cell while holding a warrant for its data
+        unsafe { &*self.direct_ptr }
+    }
+}
+
 impl<T: Scan + 'static> Gc<sync::Mutex<T>> {
     /// Call the underlying `lock` method on the inner `Mutex`
     ///
@@ -1,5 +1,31 @@
+use std::mem::ManuallyDrop;
+use std::pin::Pin;
+use std::ptr;
+
 mod deref_gc;
 mod gc;
 
 pub use deref_gc::*;
 pub use gc::*;
+
+/// `Pin::new_unchecked`, but for pointers that don't implement `Deref` (like `Gc`)
+///
+/// The caller must uphold the same contract as `Pin::new_unchecked`.
+pub(crate) unsafe fn pin_pointer<P>(pointer: P) -> Pin<P> {
+    let pointer = ManuallyDrop::new(pointer);
+    ptr::read(ptr::from_ref::<P>(&pointer).cast::<Pin<P>>())
+}
+
+/// Get at the pointer inside a `Pin`, even if it doesn't implement `Deref`
+///
+/// `Pin` is `repr(transparent)`, so this is just a cast. The caller must not use the pointer to
+/// move the pinned data.
+pub(crate) fn pinned_pointer<P>(pin: &Pin<P>) -> &P {
+    unsafe { &*ptr::from_ref(pin).cast::<P>() }
+}
+
+/// Like `pinned_pointer`, but mutable. The caller must not use the pointer to move the pinned
+/// data, or replace the pointer with one to data that isn't pinned.
+pub(crate) unsafe fn pinned_pointer_mut<P>(pin: &mut Pin<P>) -> &mut P {
+    &mut *ptr::from_mut(pin).cast::<P>()
+}
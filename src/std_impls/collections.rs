@@ -1,11 +1,12 @@
 // all 7 types in `std::collections` have been implemented
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
-use std::hash::BuildHasher;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
 use std::mem::forget;
 use std::ptr::read;
 
 use crate::marker::{GcDeref, GcDrop, GcSafe};
-use crate::{Finalize, Scan, Scanner};
+use crate::{Finalize, GcClone, GcCloner, Scan, Scanner};
 
 // For pretty much all simple collections, the collection inherits the properties of what it contains
 // (with respect to GcDeref, GcDrop and GcSafe)
@@ -55,6 +56,21 @@ unsafe impl<K: Finalize, V: Finalize, S: BuildHasher> Finalize for HashMap<K, V,
     }
 }
 
+impl<K, V, S> GcClone for HashMap<K, V, S>
+where
+    K: GcClone + Eq + Hash,
+    V: GcClone,
+    S: BuildHasher + Clone + GcSafe,
+{
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        let mut map = Self::with_capacity_and_hasher(self.len(), self.hasher().clone());
+        for (k, v) in self {
+            map.insert(k.gc_clone(cloner), v.gc_clone(cloner));
+        }
+        map
+    }
+}
+
 // HASHSET
 unsafe impl<T, S: BuildHasher> GcDeref for HashSet<T, S>
 where
@@ -96,6 +112,20 @@ unsafe impl<T: Finalize, S: BuildHasher> Finalize for HashSet<T, S> {
     }
 }
 
+impl<T, S> GcClone for HashSet<T, S>
+where
+    T: GcClone + Eq + Hash,
+    S: BuildHasher + Clone + GcSafe,
+{
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        let mut set = Self::with_capacity_and_hasher(self.len(), self.hasher().clone());
+        for e in self {
+            set.insert(e.gc_clone(cloner));
+        }
+        set
+    }
+}
+
 // Vec like structure means that it implemented `Iter<T>`
 macro_rules! sync_vec_like {
     ($t:ty) => {
@@ -121,6 +151,15 @@ macro_rules! sync_vec_like {
                 }
             }
         }
+
+        impl<T: GcClone> GcClone for $t
+        where
+            $t: FromIterator<T>,
+        {
+            fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+                self.iter().map(|e| e.gc_clone(cloner)).collect()
+            }
+        }
     };
     {$($t:ty,)*} => {
         $(sync_vec_like!($t);)*
@@ -164,6 +203,17 @@ macro_rules! sync_map_like {
                 }
             }
         }
+
+        impl<K: GcClone, V: GcClone> GcClone for $t
+        where
+            $t: FromIterator<(K, V)>,
+        {
+            fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+                self.iter()
+                    .map(|(k, v)| (k.gc_clone(cloner), v.gc_clone(cloner)))
+                    .collect()
+            }
+        }
     };
     {$($t:ty,)*} => {
         $(sync_map_like!($t);)*
@@ -228,6 +278,14 @@ macro_rules! sync_tuple {
                 $(forget($name);)*
             }
         }
+
+        impl<$($name: GcClone),*> GcClone for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+                let ($(ref $name,)*) = *self;
+                ($($name.gc_clone(cloner),)*)
+            }
+        }
     );
 }
 
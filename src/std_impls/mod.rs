@@ -27,11 +27,11 @@ mod test {
     fn cell_scans() {
         let cell2: Cell<Option<u32>> = Cell::new(None);
         let mut count = 0;
-        let mut scanner = Scanner::new(|_| {
+        let mut count_handle = |_| {
             count += 1;
-        });
+        };
+        let mut scanner = Scanner::new(&mut count_handle);
         scanner.scan(&cell2);
-        drop(scanner);
         assert_eq!(count, 0);
     }
 
@@ -42,11 +42,11 @@ mod test {
         }];
 
         let mut count = 0;
-        let mut scanner = Scanner::new(|_| {
+        let mut count_handle = |_| {
             count += 1;
-        });
+        };
+        let mut scanner = Scanner::new(&mut count_handle);
         scanner.scan(&v);
-        drop(scanner);
         assert_eq!(count, 1);
     }
 
@@ -57,12 +57,11 @@ mod test {
         });
 
         let mut count = 0;
-        let mut scanner = Scanner::new(|_| {
+        let mut count_handle = |_| {
             count += 1;
-        });
+        };
+        let mut scanner = Scanner::new(&mut count_handle);
         scanner.scan(&m);
-
-        drop(scanner);
         assert_eq!(count, 1);
     }
 
@@ -79,12 +78,11 @@ mod test {
         assert!(catch_res.is_err());
 
         let mut count = 0;
-        let mut scanner = Scanner::new(|_| {
+        let mut count_handle = |_| {
             count += 1;
-        });
+        };
+        let mut scanner = Scanner::new(&mut count_handle);
         scanner.scan(&m);
-
-        drop(scanner);
         assert_eq!(count, 1);
     }
 
@@ -95,12 +93,11 @@ mod test {
         });
 
         let mut count = 0;
-        let mut scanner = Scanner::new(|_| {
+        let mut count_handle = |_| {
             count += 1;
-        });
+        };
+        let mut scanner = Scanner::new(&mut count_handle);
         scanner.scan(&m);
-
-        drop(scanner);
         assert_eq!(count, 1);
     }
 
@@ -117,12 +114,11 @@ mod test {
         assert!(catch_res.is_err());
 
         let mut count = 0;
-        let mut scanner = Scanner::new(|_| {
+        let mut count_handle = |_| {
             count += 1;
-        });
+        };
+        let mut scanner = Scanner::new(&mut count_handle);
         scanner.scan(&m);
-
-        drop(scanner);
         assert_eq!(count, 1);
     }
 }
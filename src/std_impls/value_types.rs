@@ -1,4 +1,5 @@
 use std::collections::hash_map::RandomState;
+use std::marker::PhantomPinned;
 use std::ptr::drop_in_place;
 use std::time::{Duration, Instant};
 
@@ -18,6 +19,13 @@ macro_rules! sync_value_type {
                 drop_in_place(self);
             }
         }
+
+        impl crate::GcClone for $t {
+            #[inline]
+            fn gc_clone(&self, _: &mut crate::GcCloner) -> Self {
+                Clone::clone(self)
+            }
+        }
     };
 }
 
@@ -46,6 +54,8 @@ sync_value_type!(Duration);
 
 sync_value_type!(RandomState);
 
+sync_value_type!(PhantomPinned);
+
 #[cfg(test)]
 mod test {
     use std::mem::forget;
@@ -1,8 +1,10 @@
 use std::cell::{Cell, RefCell};
+use std::pin::Pin;
 use std::sync::{Arc, Mutex, RwLock, TryLockError};
 
 use crate::marker::{GcDeref, GcDrop, GcSafe};
-use crate::{Finalize, Scan, Scanner};
+use crate::smart_ptr::{pinned_pointer, pinned_pointer_mut};
+use crate::{Finalize, GcClone, GcCloner, Scan, Scanner};
 
 // ARC
 unsafe impl<T: ?Sized> GcDeref for Arc<T> where T: GcDeref + Send {}
@@ -28,6 +30,13 @@ unsafe impl<T: Finalize + ?Sized> Finalize for Cell<T> {
     }
 }
 
+impl<T: Copy + GcSafe> GcClone for Cell<T> {
+    fn gc_clone(&self, _: &mut GcCloner) -> Self {
+        // Like `scan`, we rely on a `Copy` type not containing a `Gc`
+        Self::new(self.get())
+    }
+}
+
 // MUTEX
 // unsafe impl<T> !GcDeref for Mutex<T> where T: GcDeref {}
 unsafe impl<T: ?Sized> GcDrop for Mutex<T> where T: GcDrop {}
@@ -63,6 +72,16 @@ unsafe impl<T: Finalize + ?Sized> Finalize for Mutex<T> {
     }
 }
 
+impl<T: GcClone> GcClone for Mutex<T> {
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        let copy = match self.lock() {
+            Ok(data) => data.gc_clone(cloner),
+            Err(poison_error) => poison_error.into_inner().gc_clone(cloner),
+        };
+        Self::new(copy)
+    }
+}
+
 // OPTION
 unsafe impl<T> GcDeref for Option<T> where T: GcDeref {}
 unsafe impl<T> GcDrop for Option<T> where T: GcDrop {}
@@ -84,6 +103,32 @@ unsafe impl<T: Finalize> Finalize for Option<T> {
     }
 }
 
+impl<T: GcClone> GcClone for Option<T> {
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        self.as_ref().map(|v| v.gc_clone(cloner))
+    }
+}
+
+// PIN
+unsafe impl<P> GcDeref for Pin<P> where P: GcDeref {}
+unsafe impl<P> GcDrop for Pin<P> where P: GcDrop {}
+unsafe impl<P> GcSafe for Pin<P> where P: GcSafe {}
+
+unsafe impl<P: Scan> Scan for Pin<P> {
+    #[inline]
+    fn scan(&self, scanner: &mut Scanner<'_>) {
+        // Scanning only looks at the pointer, so the pointee stays put
+        pinned_pointer(self).scan(scanner);
+    }
+}
+
+unsafe impl<P: Finalize> Finalize for Pin<P> {
+    unsafe fn finalize(&mut self) {
+        // Finalizing a pointer releases it, but never moves what it points to
+        pinned_pointer_mut(self).finalize();
+    }
+}
+
 // REFCELL
 // unsafe impl<T> !GcDeref for Cell<T> where T: GcDeref {}
 unsafe impl<T: ?Sized> GcDrop for RefCell<T> where T: GcDrop {}
@@ -108,6 +153,12 @@ unsafe impl<T: Finalize + ?Sized> Finalize for RefCell<T> {
     }
 }
 
+impl<T: GcClone> GcClone for RefCell<T> {
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        Self::new(self.borrow().gc_clone(cloner))
+    }
+}
+
 // RESULT
 unsafe impl<T, E> GcDeref for Result<T, E>
 where
@@ -146,6 +197,15 @@ unsafe impl<T: Finalize, E: Finalize> Finalize for Result<T, E> {
     }
 }
 
+impl<T: GcClone, E: GcClone> GcClone for Result<T, E> {
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        match self {
+            Ok(v) => Ok(v.gc_clone(cloner)),
+            Err(e) => Err(e.gc_clone(cloner)),
+        }
+    }
+}
+
 // RWLOCK
 // unsafe impl<T> !GcDeref for Mutex<T> where T: GcDeref {}
 unsafe impl<T: ?Sized> GcDrop for RwLock<T> where T: GcDrop {}
@@ -180,3 +240,13 @@ unsafe impl<T: Finalize + ?Sized> Finalize for RwLock<T> {
         }
     }
 }
+
+impl<T: GcClone> GcClone for RwLock<T> {
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        let copy = match self.read() {
+            Ok(data) => data.gc_clone(cloner),
+            Err(poison_error) => poison_error.into_inner().gc_clone(cloner),
+        };
+        Self::new(copy)
+    }
+}
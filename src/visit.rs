@@ -0,0 +1,223 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+
+use crate::collector::{GcData, COLLECTOR};
+use crate::{Gc, Scan};
+
+/// An identifier for a piece of data found while visiting a `Gc` object graph
+///
+/// Identifiers are handed out in the order data is discovered, so the root is always `#0`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GcNodeId(usize);
+
+impl GcNodeId {
+    /// The position of this node in discovery order
+    #[must_use]
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl Display for GcNodeId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// A visitor that is told about the structure of a `Gc` object graph
+///
+/// See `visit_graph` for the guarantees around how these methods are called.
+pub trait GcVisitor {
+    /// Called exactly once for each distinct piece of data reachable from the root
+    fn visit_node(&mut self, _node: GcNodeId) {}
+
+    /// Called once for each `Gc` found while scanning `from`, which points to `to`
+    ///
+    /// `visit_node` will already have been called for both ends of the edge.
+    fn visit_edge(&mut self, _from: GcNodeId, _to: GcNodeId) {}
+}
+
+/// Walk every piece of data reachable from `root`, reporting nodes and edges to `visitor`
+///
+/// Each allocation is scanned exactly once, no matter how many `Gc`s point to it, so this is safe
+/// to use on cyclic data. Reachability is found with `Scan`, so `AtomicGc`s are followed too (using
+/// whatever they point to at the time they are scanned).
+///
+/// Data is scanned while holding the same kind of guard `Gc::get` gives you. If something in the
+/// graph is mutably borrowed (or locked) while visiting, the edges behind it will be missed.
+///
+/// # Example
+/// ```
+/// use std::cell::RefCell;
+///
+/// use shredder::visit::{visit_graph, GcNodeId, GcVisitor};
+/// use shredder::{Gc, Scan};
+///
+/// #[derive(Scan)]
+/// struct Node {
+///     next: Option<Gc<RefCell<Node>>>,
+/// }
+///
+/// #[derive(Default)]
+/// struct EdgeCounter(usize);
+///
+/// impl GcVisitor for EdgeCounter {
+///     fn visit_edge(&mut self, _from: GcNodeId, _to: GcNodeId) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let a = Gc::new(RefCell::new(Node { next: None }));
+/// let b = Gc::new(RefCell::new(Node { next: Some(a.clone()) }));
+/// a.borrow_mut().next = Some(b.clone());
+///
+/// let mut counter = EdgeCounter::default();
+/// visit_graph(&a, &mut counter);
+/// assert_eq!(counter.0, 2);
+/// ```
+pub fn visit_graph<T: Scan + ?Sized, V: GcVisitor + ?Sized>(root: &Gc<T>, visitor: &mut V) {
+    let mut walk = GraphWalk::default();
+    walk.discover(root.internal_handle_ref().data().clone(), visitor);
+
+    while let Some((from, data)) = walk.to_scan.pop_front() {
+        // Collect the handles first, so we're not holding a warrant while running callbacks
+        let mut found = Vec::new();
+        COLLECTOR.scan_data(&data, |h| found.push(h));
+
+        // Null atomics don't point anywhere, so they aren't edges
+        for data in found.iter().filter_map(|h| COLLECTOR.resolve_data(h)) {
+            let to = walk.discover(data, visitor);
+            visitor.visit_edge(from, to);
+        }
+    }
+}
+
+#[derive(Default)]
+struct GraphWalk {
+    ids: NodeIds,
+    to_scan: VecDeque<(GcNodeId, Arc<GcData>)>,
+}
+
+impl GraphWalk {
+    fn discover<V: GcVisitor + ?Sized>(&mut self, data: Arc<GcData>, visitor: &mut V) -> GcNodeId {
+        let (id, is_new) = self.ids.number(&data);
+        if is_new {
+            visitor.visit_node(id);
+            self.to_scan.push_back((id, data));
+        }
+        id
+    }
+}
+
+/// Hands out `GcNodeId`s in the order data is first seen
+#[derive(Default)]
+struct NodeIds {
+    /// holding onto the `Arc`s keeps the pointers we use as keys from being reused
+    numbered: Vec<Arc<GcData>>,
+    ids: HashMap<*const GcData, GcNodeId>,
+}
+
+impl NodeIds {
+    /// Get the id of `data`, and whether this is the first time we've seen it
+    fn number(&mut self, data: &Arc<GcData>) -> (GcNodeId, bool) {
+        let next_id = GcNodeId(self.numbered.len());
+        let id = *self.ids.entry(Arc::as_ptr(data)).or_insert(next_id);
+
+        let is_new = id == next_id;
+        if is_new {
+            self.numbered.push(data.clone());
+        }
+        (id, is_new)
+    }
+}
+
+/// State shared between nested calls to `Debug`/`Display` on the same thread
+///
+/// Nodes are numbered as they're formatted, the same way `visit_graph` numbers them as they're
+/// discovered. (So there's no need to walk the whole graph before printing anything.)
+#[derive(Default)]
+struct FmtState {
+    ids: NodeIds,
+    in_progress: HashSet<*const GcData>,
+}
+
+thread_local! {
+    static FMT_STATE: RefCell<Option<FmtState>> = const { RefCell::new(None) };
+}
+
+/// Clears out `FMT_STATE` when the outermost formatting call finishes (even if it panics)
+struct FmtStateReset;
+
+impl Drop for FmtStateReset {
+    fn drop(&mut self) {
+        FMT_STATE.with(|state| *state.borrow_mut() = None);
+    }
+}
+
+/// Run `f` on the formatting state, setting it up if this is the outermost formatting call
+///
+/// Nodes are numbered as they're formatted, so the state must live as long as the returned reset.
+fn with_fmt_state<R>(f: impl FnOnce(&mut FmtState) -> R) -> (Option<FmtStateReset>, R) {
+    FMT_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let reset = if state.is_none() {
+            *state = Some(FmtState::default());
+            Some(FmtStateReset)
+        } else {
+            None
+        };
+
+        let res = f(state.as_mut().expect("formatting state must be set up"));
+        (reset, res)
+    })
+}
+
+/// Format `gc` without looking at the data inside, passing its id to `fmt_gc` if we're in the
+/// middle of formatting a graph (otherwise there's nothing to number it against)
+pub(crate) fn fmt_opaque<T, F>(gc: &Gc<T>, f: &mut Formatter<'_>, fmt_gc: F) -> fmt::Result
+where
+    T: Scan + ?Sized,
+    F: FnOnce(Option<GcNodeId>, &mut Formatter<'_>) -> fmt::Result,
+{
+    let id = FMT_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state
+            .as_mut()
+            .map(|state| state.ids.number(gc.internal_handle_ref().data()).0)
+    });
+
+    fmt_gc(id, f)
+}
+
+/// Format the data in `gc` using `fmt_data`, unless we're already in the middle of formatting it
+/// (in which case this prints `<cycle #N>`)
+pub(crate) fn fmt_cycle_aware<T, F>(gc: &Gc<T>, f: &mut Formatter<'_>, fmt_data: F) -> fmt::Result
+where
+    T: Scan + ?Sized,
+    F: FnOnce(&T, GcNodeId, &mut Formatter<'_>) -> fmt::Result,
+{
+    let data = gc.internal_handle_ref().data();
+    let key = Arc::as_ptr(data);
+
+    let (_reset, (id, is_cycle)) =
+        with_fmt_state(|state| (state.ids.number(data).0, !state.in_progress.insert(key)));
+
+    if is_cycle {
+        return write!(f, "<cycle {id}>");
+    }
+
+    let res = {
+        let guard = gc.get();
+        fmt_data(&*guard, id, f)
+    };
+
+    FMT_STATE.with(|state| {
+        if let Some(state) = state.borrow_mut().as_mut() {
+            state.in_progress.remove(&key);
+        }
+    });
+
+    res
+}
@@ -0,0 +1,98 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use shredder::{collect, set_gc_allocator, synchronize_destructors, Gc, SetAllocatorError};
+
+static TEST_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Counts the bytes it currently has allocated, and any (invalid) zero-sized requests
+struct CountingAllocator {
+    allocated: AtomicUsize,
+    zero_sized_requests: AtomicUsize,
+}
+
+impl CountingAllocator {
+    fn check_layout(&self, layout: Layout) {
+        if layout.size() == 0 {
+            self.zero_sized_requests.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.check_layout(layout);
+        self.allocated.fetch_add(layout.size(), Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.check_layout(layout);
+        self.allocated.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+static GC_MEMORY: CountingAllocator = CountingAllocator {
+    allocated: AtomicUsize::new(0),
+    zero_sized_requests: AtomicUsize::new(0),
+};
+
+// Every test in this file needs the allocator set before it allocates anything
+static SETUP: Lazy<()> = Lazy::new(|| set_gc_allocator(&GC_MEMORY).unwrap());
+
+fn gc_bytes() -> usize {
+    GC_MEMORY.allocated.load(Ordering::SeqCst)
+}
+
+#[test]
+fn gc_data_uses_the_allocator() {
+    Lazy::force(&SETUP);
+    let _guard = TEST_MUTEX.lock();
+    collect();
+    synchronize_destructors();
+    let before = gc_bytes();
+
+    let data = Gc::new([1_u64; 16].to_vec());
+    let cyclic = Gc::new_cyclic(|_| 0_u128);
+    // The `Vec` buffer isn't `Gc` data, so only the `Vec` itself counts
+    let expected = mem::size_of::<Vec<u64>>() + mem::size_of::<u128>();
+    assert_eq!(gc_bytes() - before, expected);
+
+    drop(data);
+    drop(cyclic);
+    collect();
+    synchronize_destructors();
+    assert_eq!(gc_bytes(), before);
+}
+
+#[test]
+fn zero_sized_data_skips_the_allocator() {
+    Lazy::force(&SETUP);
+    let _guard = TEST_MUTEX.lock();
+    collect();
+    synchronize_destructors();
+    let before = gc_bytes();
+
+    let unit = Gc::new(());
+    let cyclic = Gc::new_cyclic(|_| ());
+    assert_eq!(*unit.get(), ());
+    assert_eq!(gc_bytes(), before);
+
+    drop(unit);
+    drop(cyclic);
+    collect();
+    synchronize_destructors();
+    assert_eq!(gc_bytes(), before);
+    assert_eq!(GC_MEMORY.zero_sized_requests.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn allocator_cant_change_once_set() {
+    Lazy::force(&SETUP);
+    assert_eq!(set_gc_allocator(&System), Err(SetAllocatorError));
+}
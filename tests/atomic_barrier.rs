@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+
+use shredder::atomic::AtomicGc;
+use shredder::marker::{GcDrop, GcSafe};
+use shredder::{collect, run_with_gc_cleanup, Finalize, Gc, Scan, Scanner};
+
+static TEST_MUTEX: Lazy<parking_lot::Mutex<()>> = Lazy::new(|| parking_lot::Mutex::new(()));
+
+static SLOW_SCAN_ARMED: AtomicBool = AtomicBool::new(false);
+static SLOW_SCAN_STARTED: AtomicBool = AtomicBool::new(false);
+static SLOW_SCAN_FINISHED: AtomicBool = AtomicBool::new(false);
+
+/// Data that stalls the collector the first time it is scanned after being armed
+struct SlowScan;
+
+unsafe impl Scan for SlowScan {
+    fn scan(&self, _: &mut Scanner<'_>) {
+        if SLOW_SCAN_ARMED.swap(false, Ordering::SeqCst) {
+            SLOW_SCAN_STARTED.store(true, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(500));
+            SLOW_SCAN_FINISHED.store(true, Ordering::SeqCst);
+        }
+    }
+}
+unsafe impl GcSafe for SlowScan {}
+unsafe impl GcDrop for SlowScan {}
+
+#[derive(Scan, Finalize)]
+#[shredder(cant_drop)]
+struct Node {
+    value: u32,
+    check: u32,
+    next: AtomicGc<u32>,
+}
+
+#[test]
+fn atomics_dont_wait_for_marking() {
+    let _guard = TEST_MUTEX.lock();
+    run_with_gc_cleanup(|| {
+        let _slow = Gc::new(SlowScan);
+        let first = Gc::new(1_u32);
+        let atomic = AtomicGc::new(&first);
+        drop(first);
+
+        SLOW_SCAN_ARMED.store(true, Ordering::SeqCst);
+        let collector = thread::spawn(collect);
+        while !SLOW_SCAN_STARTED.load(Ordering::SeqCst) {
+            thread::yield_now();
+        }
+
+        // The collection is stalled mid-marking, but atomic operations can still go ahead
+        let second = Gc::new(2_u32);
+        atomic.store(&second, Ordering::SeqCst);
+        assert_eq!(*atomic.load(Ordering::SeqCst).get(), 2);
+        let old = atomic.swap(&Gc::new(3), Ordering::SeqCst);
+        assert!(old.ptr_eq(&second));
+        assert!(!SLOW_SCAN_FINISHED.load(Ordering::SeqCst));
+
+        collector.join().unwrap();
+        assert_eq!(*atomic.load(Ordering::SeqCst).get(), 3);
+        assert_eq!(*old.get(), 2);
+    });
+}
+
+#[test]
+fn overwritten_data_survives_concurrent_collection() {
+    let _guard = TEST_MUTEX.lock();
+    run_with_gc_cleanup(|| {
+        let root = Gc::new(1000_u32);
+        let head = Arc::new(AtomicGc::new(&Gc::new_with_finalizer(Node {
+            value: 0,
+            check: !0,
+            next: AtomicGc::new(&root),
+        })));
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let collector = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    collect();
+                }
+            })
+        };
+
+        let workers: Vec<_> = (0..4)
+            .map(|t| {
+                let head = head.clone();
+                thread::spawn(move || {
+                    for i in 0..500 {
+                        let value = t * 1000 + i;
+                        let current = head.load(Ordering::SeqCst);
+                        let next_value = {
+                            let current = current.get();
+                            assert_eq!(current.value, !current.check);
+                            current.next.load(Ordering::SeqCst)
+                        };
+                        let node = Gc::new_with_finalizer(Node {
+                            value,
+                            check: !value,
+                            next: AtomicGc::new(&next_value),
+                        });
+                        // Other threads may still be reading the node we overwrite here
+                        drop(head.swap(&node, Ordering::SeqCst));
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        stop.store(true, Ordering::SeqCst);
+        collector.join().unwrap();
+
+        let last = head.load(Ordering::SeqCst);
+        let last = last.get();
+        assert_eq!(last.value, !last.check);
+        assert_eq!(*last.next.load(Ordering::SeqCst).get(), 1000);
+    });
+}
@@ -0,0 +1,131 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+
+use shredder::atomic::AtomicOptionGc;
+use shredder::{collect, run_with_gc_cleanup, Finalize, Gc, Scan};
+
+#[derive(Scan, Finalize)]
+#[shredder(cant_drop)]
+struct StackNode {
+    value: u32,
+    next: AtomicOptionGc<StackNode>,
+}
+
+/// A Treiber stack, the classic use for a nullable atomic pointer
+#[derive(Scan)]
+#[shredder(cant_drop)]
+struct Stack {
+    head: AtomicOptionGc<StackNode>,
+}
+
+impl Stack {
+    fn push(&self, value: u32) {
+        loop {
+            let head = self.head.load(Ordering::SeqCst);
+            let node = Gc::new_with_finalizer(StackNode {
+                value,
+                next: AtomicOptionGc::new(head.as_ref()),
+            });
+            if self.head.compare_exchange(
+                head.as_ref(),
+                Some(&node),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                return;
+            }
+        }
+    }
+
+    fn pop(&self) -> Option<u32> {
+        loop {
+            let head = self.head.load(Ordering::SeqCst)?;
+            let next = head.get().next.load(Ordering::SeqCst);
+            if self.head.compare_exchange(
+                Some(&head),
+                next.as_ref(),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                return Some(head.get().value);
+            }
+        }
+    }
+}
+
+#[test]
+fn null_by_default() {
+    let atomic: AtomicOptionGc<u32> = AtomicOptionGc::default();
+    assert!(atomic.load(Ordering::SeqCst).is_none());
+    assert!(atomic.take(Ordering::SeqCst).is_none());
+
+    let data = Gc::new(1);
+    assert!(!atomic.compare_exchange(Some(&data), None, Ordering::SeqCst, Ordering::SeqCst));
+    atomic.store(Some(&data), Ordering::SeqCst);
+    assert!(atomic.load(Ordering::SeqCst).unwrap().ptr_eq(&data));
+
+    let old = atomic.swap(None, Ordering::SeqCst);
+    assert!(old.unwrap().ptr_eq(&data));
+    assert!(atomic.load(Ordering::SeqCst).is_none());
+}
+
+#[test]
+fn null_atomics_survive_collection() {
+    run_with_gc_cleanup(|| {
+        let holder = Gc::new_with_finalizer(StackNode {
+            value: 0,
+            next: AtomicOptionGc::none(),
+        });
+        collect();
+
+        let data = Gc::new_with_finalizer(StackNode {
+            value: 1,
+            next: AtomicOptionGc::none(),
+        });
+        holder.get().next.store(Some(&data), Ordering::SeqCst);
+        drop(data);
+        collect();
+
+        let next = holder.get().next.take(Ordering::SeqCst).unwrap();
+        assert_eq!(next.get().value, 1);
+    });
+}
+
+#[test]
+fn concurrent_treiber_stack() {
+    run_with_gc_cleanup(|| {
+        let stack = Arc::new(Stack {
+            head: AtomicOptionGc::none(),
+        });
+
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let stack = stack.clone();
+                thread::spawn(move || {
+                    for i in 0..250 {
+                        stack.push(t * 1000 + i);
+                        if i % 3 == 0 {
+                            collect();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        let mut popped = Vec::new();
+        while let Some(v) = stack.pop() {
+            popped.push(v);
+        }
+        popped.sort_unstable();
+
+        let mut expected: Vec<_> = (0..4)
+            .flat_map(|t| (0..250).map(move |i| t * 1000 + i))
+            .collect();
+        expected.sort_unstable();
+        assert_eq!(popped, expected);
+    });
+}
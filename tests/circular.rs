@@ -1,4 +1,6 @@
-use shredder::{Finalize, Gc, Scan};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use shredder::{collect, synchronize_destructors, DerefGc, Finalize, Gc, Scan};
 
 #[derive(Scan, Finalize)]
 struct Circular {
@@ -25,3 +27,98 @@ fn can_create_circular_finalize() {
 
     assert_eq!(circle.get().n, 146)
 }
+
+#[derive(Scan, Finalize)]
+#[shredder(can_deref, cant_drop)]
+struct DerefCircular {
+    self_ref: DerefGc<DerefCircular>,
+    n: u64,
+}
+
+#[test]
+fn can_create_circular_deref_finalize() {
+    let circle = DerefGc::new_cyclic_with_finalizer(|gc| DerefCircular {
+        self_ref: gc,
+        n: 146,
+    });
+
+    assert_eq!(circle.self_ref.self_ref.n, 146);
+    assert!(circle.self_ref.ptr_eq(&circle));
+}
+
+#[derive(Scan)]
+#[shredder(can_deref)]
+struct DerefCircularViaGc {
+    self_ref: Gc<DerefCircularViaGc>,
+    n: u64,
+}
+
+#[test]
+fn can_create_circular_deref() {
+    let circle = DerefGc::new_cyclic(|gc: DerefGc<DerefCircularViaGc>| DerefCircularViaGc {
+        self_ref: gc.into_gc(),
+        n: 146,
+    });
+
+    assert_eq!(circle.n, 146);
+    assert_eq!(circle.self_ref.get().n, 146);
+
+    // Both directions of conversion keep pointing at the same data
+    let as_deref = DerefGc::from(circle.self_ref.clone());
+    assert!(as_deref.ptr_eq(&circle));
+    assert!(as_deref.into_gc().ptr_eq(&circle.self_ref));
+}
+
+#[test]
+#[should_panic(expected = "not initialized")]
+fn deref_during_cyclic_construction_panics() {
+    let _ = DerefGc::new_cyclic(|gc: DerefGc<DerefCircularViaGc>| DerefCircularViaGc {
+        n: gc.n,
+        self_ref: gc.into_gc(),
+    });
+}
+
+#[test]
+fn can_create_circular_no_drop() {
+    let circle: Gc<Circular> = Gc::new_cyclic_no_drop(|gc| Circular {
+        self_ref: gc,
+        n: 146,
+    });
+
+    assert_eq!(circle.get().n, 146);
+    assert!(circle.get().self_ref.ptr_eq(&circle));
+}
+
+static NO_DROP_DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Scan)]
+struct CircularNoDrop {
+    self_ref: Gc<CircularNoDrop>,
+}
+
+impl Drop for CircularNoDrop {
+    fn drop(&mut self) {
+        NO_DROP_DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn circular_no_drop_never_runs_destructor() {
+    let circle: Gc<CircularNoDrop> = Gc::new_cyclic_no_drop(|gc| CircularNoDrop { self_ref: gc });
+    assert!(circle.get().self_ref.ptr_eq(&circle));
+    drop(circle);
+
+    collect();
+    synchronize_destructors();
+    assert_eq!(NO_DROP_DROPPED.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn can_create_circular_deref_no_drop() {
+    let circle = DerefGc::new_cyclic_no_drop(|gc| DerefCircular {
+        self_ref: gc,
+        n: 146,
+    });
+
+    assert_eq!(circle.self_ref.n, 146);
+}
@@ -0,0 +1,269 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::thread;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use shredder::collections::{PersistentHashMap, PersistentVec, Queue, SkipListMap, Stack};
+use shredder::{collect, run_with_gc_cleanup, Gc};
+
+const THREADS: u32 = 4;
+const PER_THREAD: u32 = 250;
+
+fn all_values() -> Vec<u32> {
+    (0..THREADS)
+        .flat_map(|t| (0..PER_THREAD).map(move |i| t * 1000 + i))
+        .collect()
+}
+
+#[test]
+fn concurrent_stack() {
+    run_with_gc_cleanup(|| {
+        let stack = Arc::new(Stack::new());
+
+        let threads: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let stack = stack.clone();
+                thread::spawn(move || {
+                    for i in 0..PER_THREAD {
+                        stack.push(&Gc::new(t * 1000 + i));
+                        if i % 50 == 0 {
+                            collect();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        let mut popped = Vec::new();
+        while let Some(v) = stack.pop() {
+            popped.push(*v.get());
+        }
+        assert!(stack.is_empty());
+
+        popped.sort_unstable();
+        assert_eq!(popped, all_values());
+    });
+}
+
+#[test]
+fn concurrent_queue_keeps_per_producer_order() {
+    run_with_gc_cleanup(|| {
+        let queue = Arc::new(Queue::new());
+
+        let producers: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for i in 0..PER_THREAD {
+                        queue.push(&Gc::new(t * 1000 + i));
+                        if i % 50 == 0 {
+                            collect();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let consumers: Vec<_> = (0..2)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    let mut seen = Vec::new();
+                    while seen.len() < (THREADS * PER_THREAD / 2) as usize {
+                        if let Some(v) = queue.pop() {
+                            seen.push(*v.get());
+                        }
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        for p in producers {
+            p.join().unwrap();
+        }
+        let mut all = Vec::new();
+        for c in consumers {
+            let seen = c.join().unwrap();
+            // Each consumer sees each producer's values in the order they were pushed
+            for t in 0..THREADS {
+                let from_t: Vec<_> = seen.iter().filter(|v| **v / 1000 == t).collect();
+                assert!(from_t.windows(2).all(|w| w[0] < w[1]));
+            }
+            all.extend(seen);
+        }
+        assert!(queue.is_empty());
+
+        all.sort_unstable();
+        assert_eq!(all, all_values());
+    });
+}
+
+#[test]
+fn skip_list_matches_btree_map() {
+    run_with_gc_cleanup(|| {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let map = SkipListMap::new();
+        let mut model = BTreeMap::new();
+
+        for i in 0..2000 {
+            let key: u32 = rng.gen_range(0..200);
+            if rng.gen_bool(0.6) {
+                let inserted = map.insert(key, Gc::new(i));
+                assert_eq!(inserted, !model.contains_key(&key));
+                model.entry(key).or_insert(i);
+            } else {
+                let removed = map.remove(&key).map(|v| *v.get());
+                assert_eq!(removed, model.remove(&key));
+            }
+
+            if i % 500 == 0 {
+                collect();
+            }
+        }
+
+        assert_eq!(map.len(), model.len());
+        let entries: Vec<_> = map.iter().map(|(k, v)| (k, *v.get())).collect();
+        let expected: Vec<_> = model.into_iter().collect();
+        assert_eq!(entries, expected);
+    });
+}
+
+#[test]
+fn concurrent_skip_list() {
+    run_with_gc_cleanup(|| {
+        let map = Arc::new(SkipListMap::new());
+
+        // Every thread inserts everything, but only one insert per key can win
+        let threads: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let map = map.clone();
+                thread::spawn(move || {
+                    let mut won = 0;
+                    for key in all_values() {
+                        if map.insert(key, Gc::new(t)) {
+                            won += 1;
+                        }
+                    }
+                    collect();
+                    // Then each thread removes its own share
+                    let mut removed = 0;
+                    for key in (0..PER_THREAD).map(|i| t * 1000 + i) {
+                        if map.remove(&key).is_some() {
+                            removed += 1;
+                        }
+                    }
+                    (won, removed)
+                })
+            })
+            .collect();
+
+        let mut total_won = 0;
+        for t in threads {
+            let (won, removed) = t.join().unwrap();
+            total_won += won;
+            assert_eq!(removed, PER_THREAD);
+        }
+        assert_eq!(total_won, THREADS * PER_THREAD);
+        assert!(map.is_empty());
+        assert_eq!(map.len(), 0);
+        assert_eq!(map.iter().count(), 0);
+    });
+}
+
+#[test]
+fn persistent_vec_versions_are_independent() {
+    run_with_gc_cleanup(|| {
+        // Enough values for a few levels of the trie
+        let size = 32 * 32 + 5;
+        let full: PersistentVec<usize> = (0..size).collect();
+        assert_eq!(full.len(), size);
+        assert!((0..size).all(|i| full.get(i) == Some(i)));
+        assert_eq!(full.get(size), None);
+
+        let changed = full.set(40, 0).unwrap();
+        assert_eq!(changed.get(40), Some(0));
+        assert_eq!(full.get(40), Some(40));
+        assert!(full.set(size, 0).is_none());
+
+        collect();
+
+        let mut shrinking = full.clone();
+        for expected_len in (0..size).rev() {
+            shrinking = shrinking.drop_last().unwrap();
+            assert_eq!(shrinking.len(), expected_len);
+            assert_eq!(shrinking.last(), expected_len.checked_sub(1));
+        }
+        assert!(shrinking.is_empty());
+        assert!(shrinking.drop_last().is_none());
+
+        // Shrinking didn't touch the original
+        assert_eq!(
+            full.iter().collect::<Vec<_>>(),
+            (0..size).collect::<Vec<_>>()
+        );
+        assert_eq!(full.push(size).last(), Some(size));
+        assert_ne!(full, changed);
+    });
+}
+
+#[test]
+fn persistent_hash_map_matches_hash_map() {
+    run_with_gc_cleanup(|| {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let mut map = PersistentHashMap::new();
+        let mut model = HashMap::new();
+        let mut snapshots = Vec::new();
+
+        for i in 0..3000_u32 {
+            let key: u32 = rng.gen_range(0..500);
+            if rng.gen_bool(0.7) {
+                map = map.insert(key, i);
+                model.insert(key, i);
+            } else {
+                map = map.remove(&key);
+                model.remove(&key);
+            }
+            assert_eq!(map.len(), model.len());
+
+            if i % 300 == 0 {
+                snapshots.push((map.clone(), model.clone()));
+                collect();
+            }
+        }
+
+        // Every version still has exactly its own contents
+        snapshots.push((map, model));
+        for (map, model) in snapshots {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable();
+            let mut expected: Vec<_> = model.into_iter().collect();
+            expected.sort_unstable();
+            assert_eq!(entries, expected);
+
+            for (k, v) in expected {
+                assert_eq!(map.get(&k), Some(v));
+            }
+        }
+    });
+}
+
+#[test]
+fn collections_can_live_in_gcs() {
+    run_with_gc_cleanup(|| {
+        let stack = Gc::new_with_finalizer(Stack::new());
+        let map = Gc::new(PersistentHashMap::new().insert(1, Gc::new(String::from("one"))));
+        stack.get().push(&map);
+        drop(map);
+
+        collect();
+
+        let map = stack.get().pop().unwrap();
+        assert_eq!(*map.get().get(&1).unwrap().get(), "one");
+    });
+}
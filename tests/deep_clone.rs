@@ -0,0 +1,111 @@
+use std::cell::RefCell;
+use std::sync::atomic::Ordering;
+
+use shredder::atomic::AtomicGc;
+use shredder::{deep_clone, Gc, GcClone, GcCloner, Scan};
+
+#[derive(Scan)]
+struct Node {
+    label: String,
+    edges: Vec<Gc<RefCell<Node>>>,
+}
+
+impl GcClone for Node {
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        Self {
+            label: self.label.gc_clone(cloner),
+            edges: self.edges.gc_clone(cloner),
+        }
+    }
+}
+
+fn node(label: &str) -> Gc<RefCell<Node>> {
+    Gc::new(RefCell::new(Node {
+        label: label.to_string(),
+        edges: Vec::new(),
+    }))
+}
+
+#[test]
+fn deep_clone_copies_data() {
+    let a = node("a");
+    let b = node("b");
+    a.borrow_mut().edges.push(b.clone());
+
+    let a_copy = deep_clone(&a);
+    assert!(!a_copy.ptr_eq(&a));
+    assert_eq!(a_copy.borrow().label, "a");
+
+    let b_copy = a_copy.borrow().edges[0].clone();
+    assert!(!b_copy.ptr_eq(&b));
+
+    // Changing the copy leaves the original alone
+    b_copy.borrow_mut().label = String::from("changed");
+    assert_eq!(b.borrow().label, "b");
+}
+
+#[test]
+fn deep_clone_preserves_sharing() {
+    let root = node("root");
+    let shared = node("shared");
+    root.borrow_mut().edges.push(shared.clone());
+    root.borrow_mut().edges.push(shared);
+
+    let copy = deep_clone(&root);
+    let copy_ref = copy.borrow();
+    assert!(copy_ref.edges[0].ptr_eq(&copy_ref.edges[1]));
+}
+
+#[test]
+fn deep_clone_preserves_cycles() {
+    let a = node("a");
+    let b = node("b");
+    a.borrow_mut().edges.push(b.clone());
+    b.borrow_mut().edges.push(a.clone());
+
+    let a_copy = deep_clone(&a);
+    let b_copy = a_copy.borrow().edges[0].clone();
+    assert_eq!(b_copy.borrow().label, "b");
+    assert!(b_copy.borrow().edges[0].ptr_eq(&a_copy));
+}
+
+#[derive(Scan)]
+#[shredder(cant_drop)]
+struct AtomicNode {
+    next: AtomicGc<AtomicNodeHolder>,
+}
+
+#[derive(Scan)]
+struct AtomicNodeHolder {
+    value: u32,
+    children: Vec<Gc<AtomicNodeHolder>>,
+}
+
+impl GcClone for AtomicNodeHolder {
+    fn gc_clone(&self, cloner: &mut GcCloner) -> Self {
+        Self {
+            value: self.value,
+            children: self.children.gc_clone(cloner),
+        }
+    }
+}
+
+#[test]
+fn deep_clone_follows_atomics() {
+    let leaf = Gc::new(AtomicNodeHolder {
+        value: 7,
+        children: Vec::new(),
+    });
+    let atomic = AtomicNode {
+        next: AtomicGc::new(&leaf),
+    };
+
+    let mut cloner = GcCloner::new();
+    let copy = atomic.next.gc_clone(&mut cloner);
+    let copied_leaf = copy.load(Ordering::SeqCst);
+    assert!(!copied_leaf.ptr_eq(&leaf));
+    assert_eq!(copied_leaf.get().value, 7);
+
+    // The same cloner hands back the same copy
+    assert!(cloner.clone_gc(&leaf).ptr_eq(&copied_leaf));
+}
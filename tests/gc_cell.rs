@@ -0,0 +1,62 @@
+use shredder::{collect, run_with_gc_cleanup, Gc, GcCell, Scan};
+
+#[derive(Debug, Default, Scan)]
+struct Node {
+    value: u32,
+    next: Option<Gc<GcCell<Node>>>,
+}
+
+#[test]
+fn borrow_rules_match_refcell() {
+    let cell = GcCell::new(5_u32);
+
+    let shared = cell.borrow();
+    assert!(cell.try_borrow().is_ok());
+    assert!(cell.try_borrow_mut().is_err());
+    drop(shared);
+
+    let mut unique = cell.try_borrow_mut().unwrap();
+    *unique += 1;
+    assert!(cell.try_borrow().is_err());
+    drop(unique);
+
+    assert_eq!(cell.into_inner(), 6);
+}
+
+#[test]
+fn replace_swap_and_take() {
+    let a = Gc::new(GcCell::new(vec![1_u32]));
+    let b = Gc::new(GcCell::new(vec![2_u32]));
+
+    assert_eq!(a.replace(vec![3]), vec![1]);
+    a.swap(&b);
+    assert_eq!(*a.borrow(), vec![2]);
+    assert_eq!(b.take(), vec![3]);
+    assert!(b.borrow().is_empty());
+
+    let cell = GcCell::new(7_u32);
+    let other = GcCell::new(8_u32);
+    cell.swap(&other);
+    assert_eq!(cell.take(), 8);
+    assert_eq!(other.replace(1), 7);
+}
+
+#[test]
+fn gc_cell_mutation_survives_collection() {
+    run_with_gc_cleanup(|| {
+        let root = Gc::new(GcCell::new(Node::default()));
+        {
+            let mut node = root.borrow_mut();
+            node.next = Some(Gc::new(GcCell::new(Node {
+                value: 9,
+                next: None,
+            })));
+            // The collector can't scan the data while it's borrowed, so this must be safe
+            collect();
+        }
+        collect();
+
+        let next = root.borrow().next.clone().unwrap();
+        assert_eq!(next.borrow().value, 9);
+    });
+}
@@ -0,0 +1,106 @@
+use std::sync::atomic::Ordering;
+
+use shredder::atomic::AtomicGc;
+use shredder::{collect, run_with_gc_cleanup, Gc, Scan};
+
+#[derive(Clone, Debug, Scan)]
+struct Node {
+    values: Vec<u32>,
+    child: Option<Gc<Node>>,
+}
+
+#[test]
+fn get_mut_on_unique_gc() {
+    run_with_gc_cleanup(|| {
+        let mut gc = Gc::new(vec![1_u32, 2]);
+        gc.get_mut().unwrap().push(3);
+        assert_eq!(*gc.get(), vec![1, 2, 3]);
+    });
+}
+
+#[test]
+fn get_mut_on_shared_gc() {
+    run_with_gc_cleanup(|| {
+        let mut gc = Gc::new(1_u32);
+        let other = gc.clone();
+        assert!(gc.get_mut().is_none());
+
+        let atomic = AtomicGc::new(&other);
+        drop(other);
+        assert!(gc.get_mut().is_none());
+
+        drop(atomic);
+        *gc.get_mut().unwrap() += 1;
+        assert_eq!(*gc.get(), 2);
+    });
+}
+
+#[test]
+fn get_mut_survives_collection() {
+    run_with_gc_cleanup(|| {
+        let mut gc = Gc::new(Node {
+            values: Vec::new(),
+            child: None,
+        });
+
+        {
+            let mut guard = gc.get_mut().unwrap();
+            guard.child = Some(Gc::new(Node {
+                values: vec![7],
+                child: None,
+            }));
+            collect();
+            guard.values.push(1);
+        }
+        collect();
+
+        let node = gc.get();
+        assert_eq!(node.values, vec![1]);
+        assert_eq!(node.child.as_ref().unwrap().get().values, vec![7]);
+    });
+}
+
+#[test]
+fn make_mut_clones_shared_data() {
+    run_with_gc_cleanup(|| {
+        let child = Gc::new(Node {
+            values: vec![7],
+            child: None,
+        });
+        let mut gc = Gc::new(Node {
+            values: vec![1],
+            child: Some(child.clone()),
+        });
+        let original = gc.clone();
+
+        gc.make_mut().values.push(2);
+        assert!(!gc.ptr_eq(&original));
+        assert_eq!(original.get().values, vec![1]);
+        assert_eq!(gc.get().values, vec![1, 2]);
+
+        // The clone is shallow, so `Gc`s inside still point to the same place
+        assert!(gc.get().child.as_ref().unwrap().ptr_eq(&child));
+
+        // Now `gc` is unique, so it isn't cloned again
+        let copy = gc.clone();
+        drop(copy);
+        gc.make_mut().values.push(3);
+        assert_eq!(gc.get().values, vec![1, 2, 3]);
+    });
+}
+
+#[test]
+fn make_mut_keeps_unique_data_in_place() {
+    run_with_gc_cleanup(|| {
+        let mut gc = Gc::new(5_u32);
+        let address = format!("{:p}", gc);
+        *gc.make_mut() += 1;
+        assert_eq!(format!("{:p}", gc), address);
+        assert_eq!(*gc.get(), 6);
+
+        let atomic = AtomicGc::new(&gc);
+        *gc.make_mut() += 1;
+        assert_eq!(*gc.get(), 7);
+        assert_eq!(*atomic.load(Ordering::SeqCst).get(), 6);
+    });
+}
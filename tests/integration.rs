@@ -3,6 +3,8 @@ use std::mem::drop;
 use std::ops::Deref;
 use std::sync::atomic::Ordering;
 use std::sync::{self, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use once_cell::sync::Lazy;
 
@@ -326,3 +328,28 @@ fn atomic_compare_and_exchange_test() {
     assert_eq!(number_of_tracked_allocations(), 0);
     assert_eq!(number_of_active_handles(), 0);
 }
+
+#[test]
+fn background_collection_sweeps_garbage() {
+    let _guard = TEST_MUTEX.lock();
+    run_with_gc_cleanup(|| {
+        // Plenty of garbage, so the background thread collects (and sweeps) without being asked
+        for i in 0..10_000 {
+            drop(Gc::new(i));
+        }
+
+        let start = Instant::now();
+        while number_of_tracked_allocations() >= 10_000 {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "the garbage was never swept"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // A manual collection sweeps up everything before it returns
+        collect();
+        synchronize_destructors();
+        assert_eq!(number_of_tracked_allocations(), 0);
+    });
+}
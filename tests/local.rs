@@ -0,0 +1,145 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync;
+use std::thread::{self, ThreadId};
+
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, MutexGuard};
+
+use shredder::{
+    collect, run_local_destructors, set_gc_reference_counting, synchronize_destructors, Gc, Scan,
+};
+
+static TEST_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Start a test with nothing left to drop (on this thread, or in the background)
+fn setup() -> MutexGuard<'static, ()> {
+    let guard = TEST_MUTEX.lock();
+    collect();
+    synchronize_destructors();
+    run_local_destructors();
+    guard
+}
+
+/// Gets `Drop` called on it, but only ever on the thread that made it
+#[derive(Scan)]
+#[shredder(cant_drop)]
+struct Counted {
+    #[shredder(unsafe_skip_gc_safe)]
+    count: Rc<Cell<usize>>,
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.count.set(self.count.get() + 1);
+    }
+}
+
+fn counted(count: &Rc<Cell<usize>>) -> Gc<Counted> {
+    Gc::new_local(Counted {
+        count: count.clone(),
+    })
+}
+
+#[test]
+fn garbage_waits_for_its_thread() {
+    let _guard = setup();
+    let count = Rc::new(Cell::new(0));
+
+    drop(counted(&count));
+    collect();
+    synchronize_destructors();
+    assert_eq!(count.get(), 0);
+
+    run_local_destructors();
+    assert_eq!(count.get(), 1);
+}
+
+#[test]
+fn allocating_runs_local_destructors() {
+    let _guard = setup();
+    let count = Rc::new(Cell::new(0));
+
+    drop(counted(&count));
+    collect();
+    synchronize_destructors();
+    assert_eq!(count.get(), 0);
+
+    let _other = Gc::new(0);
+    assert_eq!(count.get(), 1);
+}
+
+#[test]
+fn local_cycles_are_collected() {
+    #[derive(Scan)]
+    #[shredder(cant_drop)]
+    struct Node {
+        next: sync::Mutex<Option<Gc<Node>>>,
+        counted: Counted,
+    }
+
+    let _guard = setup();
+    let count = Rc::new(Cell::new(0));
+
+    let node = |next| {
+        Gc::new_local(Node {
+            next: sync::Mutex::new(next),
+            counted: Counted {
+                count: count.clone(),
+            },
+        })
+    };
+    let a = node(None);
+    let b = node(Some(a.clone()));
+    *a.get().next.lock().unwrap() = Some(b);
+    drop(a);
+
+    collect();
+    synchronize_destructors();
+    run_local_destructors();
+    assert_eq!(count.get(), 2);
+}
+
+static DROPPED_ON: Lazy<Mutex<Vec<ThreadId>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+#[derive(Scan)]
+struct RecordsThread;
+
+impl Drop for RecordsThread {
+    fn drop(&mut self) {
+        DROPPED_ON.lock().push(thread::current().id());
+    }
+}
+
+#[test]
+fn dropped_on_the_thread_that_allocated() {
+    let _guard = setup();
+    DROPPED_ON.lock().clear();
+
+    let data = Gc::new_local(RecordsThread);
+    thread::spawn(move || {
+        drop(data);
+        collect();
+        synchronize_destructors();
+        run_local_destructors();
+    })
+    .join()
+    .unwrap();
+    assert!(DROPPED_ON.lock().is_empty());
+
+    run_local_destructors();
+    assert_eq!(*DROPPED_ON.lock(), vec![thread::current().id()]);
+}
+
+#[test]
+fn freed_right_away_with_reference_counting() {
+    let _guard = setup();
+    set_gc_reference_counting(true);
+    let count = Rc::new(Cell::new(0));
+
+    let data = counted(&count);
+    drop(data);
+    assert_eq!(count.get(), 1);
+
+    set_gc_reference_counting(false);
+}
@@ -0,0 +1,113 @@
+use std::sync;
+
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, MutexGuard};
+
+use shredder::{collect, set_gc_ordered_destruction, synchronize_destructors, Gc, Scan};
+
+static TEST_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+static DESTROYED: Lazy<Mutex<Vec<usize>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+#[derive(Scan)]
+struct Node {
+    id: usize,
+    edges: sync::Mutex<Vec<Gc<Node>>>,
+}
+
+impl Node {
+    fn new(id: usize, edges: Vec<Gc<Node>>) -> Gc<Self> {
+        Gc::new(Self {
+            id,
+            edges: sync::Mutex::new(edges),
+        })
+    }
+
+    fn point_to(&self, other: &Gc<Node>) {
+        self.edges.lock().unwrap().push(other.clone());
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        DESTROYED.lock().push(self.id);
+    }
+}
+
+/// Start a test with ordered destruction on, and nothing left to destroy
+fn setup() -> MutexGuard<'static, ()> {
+    let guard = TEST_MUTEX.lock();
+    set_gc_ordered_destruction(true);
+    collect();
+    synchronize_destructors();
+    DESTROYED.lock().clear();
+    guard
+}
+
+fn collect_and_destroy() -> Vec<usize> {
+    collect();
+    synchronize_destructors();
+    DESTROYED.lock().drain(..).collect()
+}
+
+fn position(order: &[usize], id: usize) -> usize {
+    order.iter().position(|&destroyed| destroyed == id).unwrap()
+}
+
+#[test]
+fn chains_destroyed_from_the_head() {
+    let _guard = setup();
+
+    let len = 50_000;
+    let mut head = Node::new(len - 1, Vec::new());
+    for id in (0..len - 1).rev() {
+        head = Node::new(id, vec![head]);
+    }
+    drop(head);
+
+    let order = collect_and_destroy();
+    assert_eq!(order, (0..len).collect::<Vec<_>>());
+}
+
+#[test]
+fn cycles_destroyed_between_referrers_and_referents() {
+    let _guard = setup();
+
+    // 0 -> (1 <-> 2) -> 3
+    let referent = Node::new(3, Vec::new());
+    let a = Node::new(1, vec![referent]);
+    let b = Node::new(2, vec![a.clone()]);
+    a.get().point_to(&b);
+    let referrer = Node::new(0, vec![a]);
+    drop(b);
+    drop(referrer);
+
+    let order = collect_and_destroy();
+    assert_eq!(order.len(), 4);
+    assert_eq!(order[0], 0);
+    assert!(position(&order, 1) < 3 && position(&order, 2) < 3);
+    assert_eq!(order[3], 3);
+}
+
+#[derive(Scan)]
+struct Writer {
+    log: Gc<Node>,
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        // Still alive, since the log is only destroyed after us
+        let id = self.log.get().id;
+        DESTROYED.lock().push(id + 100);
+    }
+}
+
+#[test]
+fn destructors_can_use_what_they_point_to() {
+    let _guard = setup();
+
+    let log = Node::new(1, Vec::new());
+    drop(Gc::new(Writer { log }));
+
+    assert_eq!(collect_and_destroy(), vec![101, 1]);
+}
@@ -0,0 +1,134 @@
+use std::thread;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, MutexGuard};
+
+use shredder::GcPacingGoal::{self, CpuFraction, PauseTime};
+use shredder::{
+    collect, gc_trigger_percent, pause_collection, set_gc_pacing_goal, set_gc_trigger_percent,
+    synchronize_destructors, Gc, GcPauseGuard,
+};
+
+static TEST_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Start pacing towards `goal`, from a trigger percent of 1
+///
+/// Automatic collection is paused, so the only collections are the test's own
+fn setup(goal: GcPacingGoal) -> (MutexGuard<'static, ()>, GcPauseGuard) {
+    let guard = TEST_MUTEX.lock();
+    let pause = pause_collection();
+    set_gc_pacing_goal(None);
+    set_gc_trigger_percent(1.0);
+    // The first collection gives the pacer a starting time
+    collect();
+    set_gc_pacing_goal(Some(goal));
+    (guard, pause)
+}
+
+fn teardown() {
+    set_gc_pacing_goal(None);
+    set_gc_trigger_percent(0.75);
+}
+
+/// Collect `n` times, with some garbage (and a pause) in between
+///
+/// The pacer looks at each collection once it's swept, so this waits for the last sweep too
+fn collect_repeatedly(n: usize, between: Duration) {
+    for _ in 0..n {
+        for i in 0..1000 {
+            drop(Gc::new(i));
+        }
+        thread::sleep(between);
+        collect();
+    }
+    synchronize_destructors();
+}
+
+#[test]
+fn collecting_too_much_spaces_out_collections() {
+    let _guard = setup(CpuFraction(0.000_001));
+
+    collect_repeatedly(3, Duration::ZERO);
+    assert!(gc_trigger_percent() > 1.0);
+
+    teardown();
+}
+
+#[test]
+fn collecting_rarely_brings_collections_closer() {
+    let _guard = setup(CpuFraction(0.5));
+
+    collect_repeatedly(3, Duration::from_millis(100));
+    assert!(gc_trigger_percent() < 1.0);
+
+    teardown();
+}
+
+#[test]
+fn long_pauses_make_collections_more_frequent() {
+    let _guard = setup(PauseTime(Duration::from_nanos(1)));
+
+    collect_repeatedly(20, Duration::ZERO);
+    assert!((gc_trigger_percent() - 0.05).abs() < f32::EPSILON);
+
+    teardown();
+}
+
+#[test]
+fn short_pauses_make_collections_less_frequent() {
+    let _guard = setup(PauseTime(Duration::from_secs(100)));
+
+    collect_repeatedly(20, Duration::ZERO);
+    assert!((gc_trigger_percent() - 32.0).abs() < f32::EPSILON);
+
+    teardown();
+}
+
+#[test]
+fn pacer_moves_gradually() {
+    let _guard = setup(PauseTime(Duration::from_secs(100)));
+
+    collect_repeatedly(1, Duration::ZERO);
+    assert!((gc_trigger_percent() - 2.0).abs() < f32::EPSILON);
+
+    teardown();
+}
+
+#[test]
+fn collections_that_reclaim_nothing_dont_speed_up() {
+    let _guard = setup(PauseTime(Duration::from_nanos(1)));
+
+    for _ in 0..5 {
+        collect();
+    }
+    synchronize_destructors();
+    assert!((gc_trigger_percent() - 1.0).abs() < f32::EPSILON);
+
+    teardown();
+}
+
+#[test]
+fn removing_the_goal_restores_the_percent() {
+    let _guard = setup(PauseTime(Duration::from_secs(100)));
+
+    collect_repeatedly(1, Duration::ZERO);
+    assert!((gc_trigger_percent() - 2.0).abs() < f32::EPSILON);
+
+    set_gc_pacing_goal(None);
+    assert!((gc_trigger_percent() - 1.0).abs() < f32::EPSILON);
+
+    teardown();
+}
+
+#[test]
+#[should_panic]
+fn cpu_fraction_must_be_a_fraction() {
+    set_gc_pacing_goal(Some(CpuFraction(1.5)));
+}
+
+#[test]
+#[should_panic]
+fn pause_time_must_not_be_zero() {
+    set_gc_pacing_goal(Some(PauseTime(Duration::ZERO)));
+}
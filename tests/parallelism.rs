@@ -0,0 +1,133 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{self, Arc};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use rayon::ThreadPoolBuilder;
+
+use shredder::{collect, set_gc_parallelism, synchronize_destructors, Gc, GcParallelism, Scan};
+
+static TEST_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Scan)]
+struct Node {
+    next: sync::Mutex<Option<Gc<Node>>>,
+    _counter: DropCounter,
+}
+
+impl Node {
+    fn new(next: Option<Gc<Node>>) -> Self {
+        Self {
+            next: sync::Mutex::new(next),
+            _counter: DropCounter,
+        }
+    }
+}
+
+#[derive(Scan)]
+struct DropCounter;
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Make `len` nodes in a cycle, then check that collecting in `parallelism` frees exactly them
+fn collects_cycle_with(parallelism: GcParallelism, len: usize) {
+    let _guard = TEST_MUTEX.lock();
+    set_gc_parallelism(parallelism);
+    collect();
+    synchronize_destructors();
+    DROPPED.store(0, Ordering::SeqCst);
+
+    let first = Gc::new(Node::new(None));
+    let mut last = first.clone();
+    for _ in 1..len {
+        last = Gc::new(Node::new(Some(last)));
+    }
+    *first.get().next.lock().unwrap() = Some(last);
+
+    // Still reachable from `first`
+    collect();
+    synchronize_destructors();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 0);
+
+    drop(first);
+    collect();
+    synchronize_destructors();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), len);
+
+    set_gc_parallelism(GcParallelism::GlobalPool);
+}
+
+#[test]
+fn sequential_collection() {
+    collects_cycle_with(GcParallelism::Sequential, 500);
+}
+
+#[test]
+fn private_pool_collection() {
+    collects_cycle_with(GcParallelism::private_pool(2).unwrap(), 500);
+}
+
+#[test]
+fn user_pool_collection() {
+    let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+    collects_cycle_with(GcParallelism::Pool(Arc::new(pool)), 500);
+}
+
+#[test]
+fn global_pool_collection() {
+    collects_cycle_with(GcParallelism::GlobalPool, 500);
+}
+
+#[derive(Scan)]
+struct Tree {
+    children: Vec<Gc<Tree>>,
+    _counter: DropCounter,
+}
+
+fn tree(fanout: usize, depth: usize) -> Gc<Tree> {
+    let children = if depth == 0 {
+        Vec::new()
+    } else {
+        (0..fanout).map(|_| tree(fanout, depth - 1)).collect()
+    };
+    Gc::new(Tree {
+        children,
+        _counter: DropCounter,
+    })
+}
+
+#[test]
+fn many_markers_share_wide_graphs() {
+    let _guard = TEST_MUTEX.lock();
+    set_gc_parallelism(GcParallelism::private_pool(4).unwrap());
+    collect();
+    synchronize_destructors();
+    DROPPED.store(0, Ordering::SeqCst);
+
+    // 1 + 8 + 64 + 512 + 4096 nodes
+    let root = tree(8, 4);
+    for _ in 0..5 {
+        collect();
+    }
+    synchronize_destructors();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 0);
+
+    drop(root);
+    collect();
+    synchronize_destructors();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 4681);
+
+    set_gc_parallelism(GcParallelism::GlobalPool);
+}
+
+#[test]
+fn many_sweepers_share_mass_death() {
+    // Enough garbage to fill dozens of chunks, so the sweepers (and the dropper) each get some
+    collects_cycle_with(GcParallelism::private_pool(4).unwrap(), 50_000);
+}
@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, MutexGuard};
+
+use shredder::{collect, pause_collection, synchronize_destructors, Gc, Scan};
+
+static TEST_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Scan)]
+struct DropCounter;
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn setup() -> MutexGuard<'static, ()> {
+    let guard = TEST_MUTEX.lock();
+    collect();
+    synchronize_destructors();
+    DROPPED.store(0, Ordering::SeqCst);
+    guard
+}
+
+/// Make enough garbage that the trigger will ask for a collection
+fn make_garbage() {
+    for _ in 0..10_000 {
+        drop(Gc::new(DropCounter));
+    }
+}
+
+/// Wait (a while) for an automatic collection to get rid of some garbage
+fn automatically_collected() -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(10) {
+        if DROPPED.load(Ordering::SeqCst) > 0 {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    false
+}
+
+#[test]
+fn deferred_collection_runs_on_resume() {
+    let _guard = setup();
+
+    let pause = pause_collection();
+    make_garbage();
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 0);
+
+    drop(pause);
+    assert!(automatically_collected());
+}
+
+#[test]
+fn nested_pauses() {
+    let _guard = setup();
+
+    let outer = pause_collection();
+    let inner = thread::spawn(pause_collection).join().unwrap();
+    make_garbage();
+
+    drop(inner);
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 0);
+
+    drop(outer);
+    assert!(automatically_collected());
+}
+
+#[test]
+fn collect_still_collects() {
+    let _guard = setup();
+
+    let _pause = pause_collection();
+    drop(Gc::new(DropCounter));
+    collect();
+    synchronize_destructors();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 1);
+}
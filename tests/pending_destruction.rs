@@ -0,0 +1,114 @@
+use std::mem::size_of;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use parking_lot::{Condvar, Mutex, MutexGuard};
+
+use shredder::{
+    collect, number_of_pending_destructions, pending_destruction_bytes,
+    set_gc_pending_destruction_limit, synchronize_destructors, Gc, PendingDestructionLimit, Scan,
+};
+
+static TEST_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// While closed, destructors wait for it to open
+static GATE: Lazy<(Mutex<bool>, Condvar)> = Lazy::new(|| (Mutex::new(true), Condvar::new()));
+
+fn set_gate(open: bool) {
+    *GATE.0.lock() = open;
+    GATE.1.notify_all();
+}
+
+#[derive(Scan)]
+struct Slow {
+    _payload: (u64, u64, u64, u64),
+}
+
+impl Drop for Slow {
+    fn drop(&mut self) {
+        let mut open = GATE.0.lock();
+        while !*open {
+            GATE.1.wait(&mut open);
+        }
+    }
+}
+
+/// Make `n` pieces of garbage that can't be destroyed until the gate opens
+fn stuck_garbage(n: usize) -> MutexGuard<'static, ()> {
+    let guard = TEST_MUTEX.lock();
+    collect();
+    synchronize_destructors();
+
+    set_gate(false);
+    for _ in 0..n {
+        drop(Gc::new(Slow {
+            _payload: (0, 0, 0, 0),
+        }));
+    }
+    collect();
+    guard
+}
+
+#[test]
+fn pending_destructions_are_counted() {
+    let _guard = stuck_garbage(10);
+
+    assert_eq!(number_of_pending_destructions(), 10);
+    assert!(pending_destruction_bytes() >= 10 * size_of::<Slow>());
+
+    set_gate(true);
+    synchronize_destructors();
+    assert_eq!(number_of_pending_destructions(), 0);
+    assert_eq!(pending_destruction_bytes(), 0);
+}
+
+#[test]
+fn allocation_waits_for_the_backlog() {
+    let _guard = stuck_garbage(5);
+    set_gc_pending_destruction_limit(PendingDestructionLimit {
+        objects: Some(2),
+        bytes: None,
+    });
+
+    let allocated = Arc::new(AtomicBool::new(false));
+    let allocator = {
+        let allocated = allocated.clone();
+        thread::spawn(move || {
+            let _data = Gc::new(0);
+            allocated.store(true, Ordering::SeqCst);
+        })
+    };
+
+    thread::sleep(Duration::from_millis(100));
+    assert!(!allocated.load(Ordering::SeqCst));
+
+    set_gate(true);
+    allocator.join().unwrap();
+    assert!(allocated.load(Ordering::SeqCst));
+    assert!(number_of_pending_destructions() <= 2);
+
+    set_gc_pending_destruction_limit(PendingDestructionLimit::default());
+    synchronize_destructors();
+}
+
+#[test]
+fn raising_the_limit_lets_allocation_continue() {
+    let _guard = stuck_garbage(5);
+    set_gc_pending_destruction_limit(PendingDestructionLimit {
+        objects: None,
+        bytes: Some(size_of::<Slow>()),
+    });
+
+    let allocator = thread::spawn(|| drop(Gc::new(0)));
+    thread::sleep(Duration::from_millis(100));
+    assert!(!allocator.is_finished());
+
+    set_gc_pending_destruction_limit(PendingDestructionLimit::default());
+    allocator.join().unwrap();
+
+    set_gate(true);
+    synchronize_destructors();
+}
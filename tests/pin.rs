@@ -0,0 +1,75 @@
+use std::cell::Cell;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::ptr;
+
+use shredder::{collect, number_of_tracked_allocations, run_with_gc_cleanup, DerefGc, Gc, Scan};
+
+/// Remembers its own address, so we can tell if it has moved
+#[derive(Scan)]
+struct SelfAware {
+    address: Cell<usize>,
+    _pinned: PhantomPinned,
+}
+
+impl SelfAware {
+    fn new() -> Self {
+        Self {
+            address: Cell::new(0),
+            _pinned: PhantomPinned,
+        }
+    }
+
+    fn init(self: Pin<&Self>) {
+        self.address.set(ptr::addr_of!(*self) as usize);
+    }
+
+    fn is_at_home(self: Pin<&Self>) -> bool {
+        self.address.get() == ptr::addr_of!(*self) as usize
+    }
+}
+
+#[derive(Scan)]
+struct Holder {
+    pinned: Pin<Gc<SelfAware>>,
+}
+
+#[test]
+fn pinned_data_stays_put() {
+    run_with_gc_cleanup(|| {
+        let pinned = Gc::pin(SelfAware::new());
+        Gc::get_pinned(&pinned).as_ref().init();
+
+        let holder = Gc::new(Holder {
+            pinned: pinned.clone(),
+        });
+        drop(pinned);
+        collect();
+
+        let guard = holder.get();
+        assert!(Gc::get_pinned(&guard.pinned).as_ref().is_at_home());
+    });
+}
+
+#[test]
+fn pinned_data_is_collected() {
+    run_with_gc_cleanup(|| {
+        let before = number_of_tracked_allocations();
+        let holder = Gc::new(Holder {
+            pinned: Gc::pin(SelfAware::new()),
+        });
+        assert_eq!(number_of_tracked_allocations(), before + 2);
+
+        drop(holder);
+        collect();
+        assert_eq!(number_of_tracked_allocations(), before);
+    });
+}
+
+#[test]
+fn pinned_deref_gc() {
+    run_with_gc_cleanup(|| {
+        let pinned = DerefGc::pin(5_u32);
+        assert_eq!(*pinned.as_ref(), 5);
+    });
+}
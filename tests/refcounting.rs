@@ -0,0 +1,228 @@
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{self, Arc};
+use std::thread;
+
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, MutexGuard};
+
+use shredder::atomic::AtomicGc;
+use shredder::{collect, set_gc_reference_counting, synchronize_destructors, Gc, Scan};
+
+static TEST_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Scan)]
+struct Node {
+    next: sync::Mutex<Option<Gc<Node>>>,
+    _counter: DropCounter,
+}
+
+impl Node {
+    fn new(next: Option<Gc<Node>>) -> Self {
+        Self {
+            next: sync::Mutex::new(next),
+            _counter: DropCounter,
+        }
+    }
+}
+
+#[derive(Scan)]
+struct DropCounter;
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn dropped() -> usize {
+    DROPPED.load(Ordering::SeqCst)
+}
+
+/// Start a test with reference counting set to `enabled`, and nothing left to drop
+fn setup(enabled: bool) -> MutexGuard<'static, ()> {
+    let guard = TEST_MUTEX.lock();
+    set_gc_reference_counting(enabled);
+    collect();
+    synchronize_destructors();
+    DROPPED.store(0, Ordering::SeqCst);
+    guard
+}
+
+#[test]
+fn freed_when_last_handle_dropped() {
+    let _guard = setup(true);
+
+    let a = Gc::new(Node::new(None));
+    let b = a.clone();
+    drop(a);
+    assert_eq!(dropped(), 0);
+
+    drop(b);
+    assert_eq!(dropped(), 1);
+}
+
+#[test]
+fn freed_on_other_threads() {
+    let _guard = setup(true);
+
+    let data = Gc::new(Node::new(None));
+    let other = data.clone();
+    thread::spawn(move || drop(other)).join().unwrap();
+    assert_eq!(dropped(), 0);
+
+    drop(data);
+    assert_eq!(dropped(), 1);
+}
+
+#[test]
+fn long_chains_are_freed_without_recursion() {
+    let _guard = setup(true);
+
+    let len = 100_000;
+    let mut head = Gc::new(Node::new(None));
+    for _ in 1..len {
+        head = Gc::new(Node::new(Some(head)));
+    }
+    assert_eq!(dropped(), 0);
+
+    drop(head);
+    assert_eq!(dropped(), len);
+}
+
+#[test]
+fn cycles_are_left_for_collection() {
+    let _guard = setup(true);
+
+    let a = Gc::new(Node::new(None));
+    let b = Gc::new(Node::new(Some(a.clone())));
+    *a.get().next.lock().unwrap() = Some(b.clone());
+
+    drop(a);
+    drop(b);
+    assert_eq!(dropped(), 0);
+
+    collect();
+    synchronize_destructors();
+    assert_eq!(dropped(), 2);
+
+    // Dropping the cycle's handles doesn't free anything twice
+    collect();
+    synchronize_destructors();
+    assert_eq!(dropped(), 2);
+}
+
+#[test]
+fn atomic_data_is_left_for_collection() {
+    let _guard = setup(true);
+
+    let data = Gc::new(Node::new(None));
+    let atomic = Arc::new(AtomicGc::new(&data));
+    drop(data);
+
+    let loaded = atomic.load(Ordering::SeqCst);
+    drop(loaded);
+    assert_eq!(dropped(), 0);
+
+    drop(atomic);
+    collect();
+    synchronize_destructors();
+    assert_eq!(dropped(), 1);
+}
+
+#[test]
+fn off_by_default() {
+    let _guard = setup(false);
+
+    let data = Gc::new(Node::new(None));
+    drop(data);
+    assert_eq!(dropped(), 0);
+
+    collect();
+    synchronize_destructors();
+    assert_eq!(dropped(), 1);
+}
+
+/// Unwraps the rest of its list when dropped, rather than dropping it recursively
+#[derive(Scan)]
+struct Link {
+    next: Option<Gc<Link>>,
+    _counter: DropCounter,
+}
+
+impl Drop for Link {
+    fn drop(&mut self) {
+        let mut next = self.next.take();
+        while let Some(link) = next {
+            match link.try_unwrap() {
+                Ok(mut link) => next = link.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+#[test]
+fn destructors_can_unwrap() {
+    let _guard = setup(true);
+
+    let mut head = Gc::new(Link {
+        next: None,
+        _counter: DropCounter,
+    });
+    for _ in 1..100 {
+        head = Gc::new(Link {
+            next: Some(head),
+            _counter: DropCounter,
+        });
+    }
+
+    drop(head);
+    assert_eq!(dropped(), 100);
+}
+
+static SEEN_BY_DESTRUCTOR: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Scan)]
+struct Incrementer {
+    count: Gc<u32>,
+}
+
+impl Drop for Incrementer {
+    fn drop(&mut self) {
+        if let Some(mut count) = self.count.get_mut() {
+            *count += 1;
+            SEEN_BY_DESTRUCTOR.store(*count, Ordering::SeqCst);
+        }
+    }
+}
+
+#[test]
+fn destructors_can_get_mut() {
+    let _guard = setup(true);
+
+    drop(Gc::new(Incrementer { count: Gc::new(41) }));
+    assert_eq!(SEEN_BY_DESTRUCTOR.load(Ordering::SeqCst), 42);
+}
+
+#[derive(Scan)]
+struct Collects {
+    _counter: DropCounter,
+}
+
+impl Drop for Collects {
+    fn drop(&mut self) {
+        collect();
+    }
+}
+
+#[test]
+fn destructors_can_collect() {
+    let _guard = setup(true);
+
+    drop(Gc::new(Collects {
+        _counter: DropCounter,
+    }));
+    assert_eq!(dropped(), 1);
+}
@@ -0,0 +1,101 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, MutexGuard};
+
+use shredder::{
+    collect, report_idle, set_gc_schedule, synchronize_destructors, Gc, GcSchedule, Scan,
+};
+
+static TEST_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Scan)]
+struct DropCounter;
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn setup(schedule: GcSchedule) -> MutexGuard<'static, ()> {
+    let guard = TEST_MUTEX.lock();
+    collect();
+    synchronize_destructors();
+    DROPPED.store(0, Ordering::SeqCst);
+    set_gc_schedule(schedule);
+    guard
+}
+
+fn teardown() {
+    set_gc_schedule(GcSchedule::default());
+}
+
+/// Make a little garbage, not nearly enough for the trigger to ask for a collection
+fn make_garbage() {
+    for _ in 0..10 {
+        drop(Gc::new(DropCounter));
+    }
+}
+
+/// Wait (a while) for the background thread to collect the garbage by itself
+fn collected_in_background() -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(10) {
+        if DROPPED.load(Ordering::SeqCst) > 0 {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    false
+}
+
+#[test]
+fn interval_collects_without_allocation() {
+    let _guard = setup(GcSchedule {
+        interval: Some(Duration::from_millis(100)),
+        idle_after: None,
+    });
+
+    make_garbage();
+    assert!(collected_in_background());
+
+    teardown();
+}
+
+#[test]
+fn idle_after_collects_once_allocation_stops() {
+    let _guard = setup(GcSchedule {
+        interval: None,
+        idle_after: Some(Duration::from_millis(100)),
+    });
+
+    make_garbage();
+    assert!(collected_in_background());
+
+    teardown();
+}
+
+#[test]
+fn reporting_idle_collects() {
+    let _guard = setup(GcSchedule::default());
+
+    make_garbage();
+    report_idle();
+    assert!(collected_in_background());
+
+    teardown();
+}
+
+#[test]
+#[should_panic]
+fn scheduled_durations_must_not_be_zero() {
+    set_gc_schedule(GcSchedule {
+        interval: Some(Duration::ZERO),
+        idle_after: None,
+    });
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "serde")]
+
+use std::cell::RefCell;
+use std::sync::atomic::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use shredder::atomic::{AtomicGc, AtomicOptionGc};
+use shredder::serde_graph::GcGraph;
+use shredder::{DerefGc, Gc, Scan};
+
+#[derive(Scan, Serialize, Deserialize)]
+struct Node {
+    label: String,
+    edges: Vec<Gc<RefCell<Node>>>,
+}
+
+fn node(label: &str) -> Gc<RefCell<Node>> {
+    Gc::new(RefCell::new(Node {
+        label: label.to_string(),
+        edges: Vec::new(),
+    }))
+}
+
+#[test]
+fn round_trip_preserves_cycles_and_sharing() {
+    let a = node("a");
+    let b = node("b");
+    a.borrow_mut().edges.push(b.clone());
+    a.borrow_mut().edges.push(b.clone());
+    b.borrow_mut().edges.push(a.clone());
+
+    let json = serde_json::to_string(&a).unwrap();
+    let a_copy: Gc<RefCell<Node>> = serde_json::from_str(&json).unwrap();
+
+    assert!(!a_copy.ptr_eq(&a));
+    assert_eq!(a_copy.borrow().label, "a");
+
+    let edges = a_copy.borrow().edges.clone();
+    assert_eq!(edges.len(), 2);
+    assert!(edges[0].ptr_eq(&edges[1]));
+    assert_eq!(edges[0].borrow().label, "b");
+    assert!(edges[0].borrow().edges[0].ptr_eq(&a_copy));
+}
+
+#[test]
+fn separate_values_dont_share_ids() {
+    let shared = Gc::new(5_u32);
+    let list = vec![shared.clone(), shared];
+
+    // Without `GcGraph`, each `Gc` gets its own graph
+    let json = serde_json::to_string(&list).unwrap();
+    let copy: Vec<Gc<u32>> = serde_json::from_str(&json).unwrap();
+    assert!(!copy[0].ptr_eq(&copy[1]));
+
+    let json = serde_json::to_string(&GcGraph(&list)).unwrap();
+    let GcGraph(copy): GcGraph<Vec<Gc<u32>>> = serde_json::from_str(&json).unwrap();
+    assert!(copy[0].ptr_eq(&copy[1]));
+}
+
+#[test]
+fn deref_and_atomic_gcs_round_trip() {
+    let value = Gc::new(11_u32);
+    let deref = DerefGc::new(12_u32);
+    let atomic = AtomicGc::new(&value);
+
+    let json = serde_json::to_string(&GcGraph((&value, &deref, &atomic))).unwrap();
+    let GcGraph((value_copy, deref_copy, atomic_copy)): GcGraph<(
+        Gc<u32>,
+        DerefGc<u32>,
+        AtomicGc<u32>,
+    )> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(*value_copy.get(), 11);
+    assert_eq!(*deref_copy, 12);
+    assert!(atomic_copy.load(Ordering::SeqCst).ptr_eq(&value_copy));
+}
+
+#[test]
+fn bad_references_are_errors() {
+    let res: Result<Gc<u32>, _> = serde_json::from_str(r#"{"Ref":3}"#);
+    assert!(res.is_err());
+
+    let res: Result<GcGraph<(Gc<u32>, Gc<u32>)>, _> =
+        serde_json::from_str(r#"[{"Def":[0,1]},{"Def":[0,2]}]"#);
+    assert!(res.is_err());
+}
+
+#[test]
+fn atomic_option_gcs_round_trip() {
+    let value = Gc::new(3_u32);
+    let atomics = vec![AtomicOptionGc::new(Some(&value)), AtomicOptionGc::none()];
+
+    let json = serde_json::to_string(&GcGraph((&value, &atomics))).unwrap();
+    let GcGraph((value_copy, atomics_copy)): GcGraph<(Gc<u32>, Vec<AtomicOptionGc<u32>>)> =
+        serde_json::from_str(&json).unwrap();
+
+    let loaded = atomics_copy[0].load(Ordering::SeqCst).unwrap();
+    assert!(loaded.ptr_eq(&value_copy));
+    assert!(atomics_copy[1].load(Ordering::SeqCst).is_none());
+}
@@ -0,0 +1,121 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use shredder::{collect, synchronize_destructors, AllocError, Gc, Scan};
+
+static TEST_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+// An odd size, so nothing else makes allocations this big
+const BIG: usize = 50_021;
+
+/// Lets through at most `BIG_BUDGET` allocations of the test's big values at a time (while limited)
+struct BudgetAllocator;
+
+// Just the big values, since the collector makes its own large allocations too
+fn is_big(layout: Layout) -> bool {
+    (BIG..BIG + 64).contains(&layout.size())
+}
+
+static LIMITED: AtomicBool = AtomicBool::new(false);
+static BIG_BUDGET: AtomicUsize = AtomicUsize::new(0);
+static BIG_LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for BudgetAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if !is_big(layout) {
+            return System.alloc(layout);
+        }
+
+        let live = BIG_LIVE.fetch_add(1, Ordering::SeqCst);
+        if LIMITED.load(Ordering::SeqCst) && live >= BIG_BUDGET.load(Ordering::SeqCst) {
+            BIG_LIVE.fetch_sub(1, Ordering::SeqCst);
+            return std::ptr::null_mut();
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if is_big(layout) {
+            BIG_LIVE.fetch_sub(1, Ordering::SeqCst);
+        }
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: BudgetAllocator = BudgetAllocator;
+
+#[derive(Scan)]
+struct Big {
+    #[shredder(skip_scan, unsafe_skip_gc_safe, unsafe_skip_gc_drop)]
+    bytes: [u8; BIG],
+}
+
+fn big() -> Big {
+    Big { bytes: [7; BIG] }
+}
+
+/// Run `f` with room for `budget` more big allocations
+fn with_budget<F: FnOnce()>(budget: usize, f: F) {
+    let _guard = TEST_MUTEX.lock();
+    collect();
+    synchronize_destructors();
+
+    BIG_BUDGET.store(BIG_LIVE.load(Ordering::SeqCst) + budget, Ordering::SeqCst);
+    LIMITED.store(true, Ordering::SeqCst);
+    f();
+    LIMITED.store(false, Ordering::SeqCst);
+}
+
+#[test]
+fn try_new_reports_failure() {
+    with_budget(1, || {
+        let first = Gc::try_new(big()).unwrap();
+        assert_eq!(first.get().bytes[0], 7);
+
+        // `first` is still live, so collecting can't help
+        assert_eq!(Gc::try_new(big()).err(), Some(AllocError));
+    });
+}
+
+#[test]
+fn try_new_collects_garbage_before_failing() {
+    with_budget(2, || {
+        let garbage = Gc::try_new(big()).unwrap();
+        let live = Gc::try_new(big()).unwrap();
+        drop(garbage);
+
+        // Only fits if `garbage` is collected (and freed) first
+        let replacement = Gc::try_new(big()).unwrap();
+        assert_eq!(replacement.get().bytes[BIG - 1], 7);
+        assert_eq!(live.get().bytes[BIG - 1], 7);
+    });
+}
+
+#[derive(Scan)]
+struct BigCycle {
+    me: Gc<BigCycle>,
+    #[shredder(skip_scan, unsafe_skip_gc_safe, unsafe_skip_gc_drop)]
+    bytes: [u8; BIG],
+}
+
+#[test]
+fn try_new_cyclic_skips_closure_on_failure() {
+    with_budget(0, || {
+        let res: Result<Gc<BigCycle>, _> =
+            Gc::try_new_cyclic(|_| panic!("shouldn't be called without memory"));
+        assert!(res.is_err());
+    });
+
+    with_budget(1, || {
+        let cycle = Gc::try_new_cyclic(|me| BigCycle {
+            me,
+            bytes: [1; BIG],
+        })
+        .unwrap();
+        assert!(cycle.get().me.ptr_eq(&cycle));
+    });
+}
@@ -0,0 +1,117 @@
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use shredder::atomic::AtomicGc;
+use shredder::{collect, run_with_gc_cleanup, synchronize_destructors, Gc, Scan};
+
+#[derive(Debug, Scan)]
+struct Node {
+    value: u32,
+    next: Option<Gc<RefCell<Node>>>,
+}
+
+#[test]
+fn unique_gc_unwraps() {
+    run_with_gc_cleanup(|| {
+        let gc = Gc::new(String::from("hello"));
+        assert_eq!(gc.try_unwrap().ok().as_deref(), Some("hello"));
+
+        let boxed: Gc<Vec<u32>> = Gc::from_box(Box::new(vec![1, 2, 3]));
+        assert_eq!(boxed.into_inner(), Some(vec![1, 2, 3]));
+    });
+}
+
+#[test]
+fn shared_gc_doesnt_unwrap() {
+    run_with_gc_cleanup(|| {
+        let gc = Gc::new(5_u32);
+        let other = gc.clone();
+
+        let gc = gc.try_unwrap().unwrap_err();
+        assert_eq!(*gc.get(), 5);
+
+        drop(other);
+        assert_eq!(gc.into_inner(), Some(5));
+    });
+}
+
+#[test]
+fn gc_inside_other_data_counts_as_sharing() {
+    run_with_gc_cleanup(|| {
+        let outer = Gc::new(RefCell::new(Node {
+            value: 1,
+            next: None,
+        }));
+        let inner = Gc::new(RefCell::new(Node {
+            value: 2,
+            next: None,
+        }));
+
+        outer.borrow_mut().next = Some(inner.clone());
+        let inner = inner.try_unwrap().unwrap_err();
+
+        outer.borrow_mut().next = None;
+        let node = inner.into_inner().unwrap().into_inner();
+        assert_eq!(node.value, 2);
+    });
+}
+
+#[test]
+fn atomic_gc_counts_as_sharing() {
+    run_with_gc_cleanup(|| {
+        let gc = Gc::new(5_u32);
+        let atomic = AtomicGc::new(&gc);
+
+        let gc = gc.try_unwrap().unwrap_err();
+        assert!(atomic.load(Ordering::SeqCst).ptr_eq(&gc));
+
+        drop(atomic);
+        assert_eq!(gc.into_inner(), Some(5));
+    });
+}
+
+#[test]
+fn unwrapped_data_is_not_dropped_by_collector() {
+    struct CountDrops(Arc<AtomicUsize>);
+
+    unsafe impl Scan for CountDrops {
+        fn scan(&self, _: &mut shredder::Scanner<'_>) {}
+    }
+    unsafe impl shredder::marker::GcSafe for CountDrops {}
+    unsafe impl shredder::marker::GcDrop for CountDrops {}
+
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    run_with_gc_cleanup(|| {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let gc = Gc::new(CountDrops(drops.clone()));
+
+        let value = gc.try_unwrap().ok().unwrap();
+        collect();
+        synchronize_destructors();
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+        drop(value);
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+fn cyclic_data_unwraps_once_cycle_is_broken() {
+    run_with_gc_cleanup(|| {
+        let a = Gc::new(RefCell::new(Node {
+            value: 1,
+            next: None,
+        }));
+        a.borrow_mut().next = Some(a.clone());
+
+        let a = a.try_unwrap().unwrap_err();
+        a.borrow_mut().next = None;
+        assert_eq!(a.into_inner().unwrap().into_inner().value, 1);
+    });
+}
@@ -0,0 +1,119 @@
+use std::cell::RefCell;
+use std::fmt::{self, Debug, Formatter};
+
+use shredder::visit::{visit_graph, GcNodeId, GcVisitor};
+use shredder::{Gc, Scan};
+
+#[derive(Debug, Scan)]
+struct Node {
+    label: String,
+    edges: Vec<Gc<RefCell<Node>>>,
+}
+
+fn node(label: &str) -> Gc<RefCell<Node>> {
+    Gc::new(RefCell::new(Node {
+        label: label.to_string(),
+        edges: Vec::new(),
+    }))
+}
+
+#[derive(Default)]
+struct Recorder {
+    nodes: Vec<GcNodeId>,
+    edges: Vec<(usize, usize)>,
+}
+
+impl GcVisitor for Recorder {
+    fn visit_node(&mut self, node: GcNodeId) {
+        self.nodes.push(node);
+    }
+
+    fn visit_edge(&mut self, from: GcNodeId, to: GcNodeId) {
+        self.edges.push((from.index(), to.index()));
+    }
+}
+
+#[test]
+fn visit_reports_each_node_once() {
+    let a = node("a");
+    let b = node("b");
+    let c = node("c");
+    a.borrow_mut().edges.push(b.clone());
+    a.borrow_mut().edges.push(c.clone());
+    b.borrow_mut().edges.push(c.clone());
+    c.borrow_mut().edges.push(a.clone());
+
+    let mut recorder = Recorder::default();
+    visit_graph(&a, &mut recorder);
+
+    assert_eq!(recorder.nodes.len(), 3);
+    assert_eq!(recorder.nodes[0].index(), 0);
+    assert_eq!(recorder.edges, vec![(0, 1), (0, 2), (1, 2), (2, 0)]);
+}
+
+#[test]
+fn debug_handles_cycles() {
+    let a = node("a");
+    let b = node("b");
+    a.borrow_mut().edges.push(b.clone());
+    b.borrow_mut().edges.push(a.clone());
+    b.borrow_mut().edges.push(b.clone());
+
+    let printed = format!("{:?}", a);
+    assert!(printed.starts_with("Gc(#0, "));
+    assert!(printed.contains("Gc(#1, "));
+    assert!(printed.contains("<cycle #0>"));
+    assert!(printed.contains("<cycle #1>"));
+
+    // Formatting state shouldn't leak between calls
+    assert_eq!(printed, format!("{:?}", a));
+}
+
+#[test]
+fn debug_shared_but_acyclic_data_is_printed_in_full() {
+    let shared = node("shared");
+    let a = node("a");
+    a.borrow_mut().edges.push(shared.clone());
+    a.borrow_mut().edges.push(shared);
+
+    let printed = format!("{:?}", a);
+    assert_eq!(printed.matches("shared").count(), 2);
+    assert!(!printed.contains("<cycle"));
+}
+
+#[derive(Scan)]
+struct NotDebug;
+
+#[derive(Scan)]
+struct Holder {
+    shared: Vec<Gc<NotDebug>>,
+    node: Gc<RefCell<Node>>,
+}
+
+impl Debug for Holder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let shared: Vec<_> = self.shared.iter().map(Gc::debug_opaque).collect();
+        f.debug_struct("Holder")
+            .field("shared", &shared)
+            .field("node", &self.node)
+            .finish()
+    }
+}
+
+#[test]
+fn opaque_gcs_are_tagged_inside_a_graph() {
+    let shared = Gc::new(NotDebug);
+    let holder = Holder {
+        shared: vec![shared.clone(), shared],
+        node: node("a"),
+    };
+
+    // Nothing to number the opaque `Gc`s against until the node starts a graph
+    let printed = format!("{:?}", holder);
+    assert!(printed.starts_with("Holder { shared: [Gc(0x"));
+    assert!(printed.contains("node: Gc(#0, "));
+
+    let printed = format!("{:?}", Gc::new(holder));
+    assert_eq!(printed.matches("Gc(#1, ").count(), 2);
+    assert!(printed.contains("Gc(#2, "));
+}